@@ -0,0 +1,49 @@
+//! embeds sources of the standard library (`std` directory) into the interpreter binary
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+fn collect_modules(dir: &Path, result: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_modules(&path, result)?;
+        } else if path.extension().is_some_and(|e| e == "txt") {
+            result.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn main() -> io::Result<()> {
+    let root = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let std_dir = root.join("std");
+    println!("cargo:rerun-if-changed={}", std_dir.display());
+
+    let mut modules = vec![];
+    collect_modules(&std_dir, &mut modules)?;
+    modules.sort();
+
+    let mut table = String::from("&[\n");
+    for path in modules {
+        let key = path
+            .strip_prefix(&root)
+            .unwrap()
+            .components()
+            .map(|c| c.as_os_str().to_str().unwrap())
+            .collect::<Vec<_>>()
+            .join("/");
+        table.push_str(&format!(
+            "    ({:?}, include_str!({:?})),\n",
+            key,
+            path.display().to_string()
+        ));
+    }
+    table.push(']');
+
+    fs::write(
+        PathBuf::from(env::var("OUT_DIR").unwrap()).join("stdlib.rs"),
+        table,
+    )
+}
@@ -1,7 +1,12 @@
 use super::tree_rewriter::Rewriter;
+use crate::data::value_ops::{checked_pow, shift_fits};
+use crate::parsing::ast::Expr;
 use crate::parsing::ast::{Program, Stmt};
 use crate::parsing::lexer::{Token, TokenKind};
-use crate::Expr;
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::{ToPrimitive, Zero};
+use ordered_float::NotNan;
 
 pub(super) struct Folder {}
 
@@ -19,6 +24,27 @@ impl Rewriter<String> for Folder {
         Ok(Stmt::Assert(keyword, expr))
     }
 
+    fn visit_unary_expr(&mut self, op: Token, arg: Box<Expr>) -> Result<Expr, String> {
+        let arg = self.visit_expr(*arg)?;
+        match (&op.kind, &arg) {
+            (TokenKind::BitNot, Expr::Number(n)) => Ok(Expr::Number(Token {
+                position: op.position,
+                kind: integer_token_kind(!n.get_integer().unwrap()),
+            })),
+            //negative literals become single constants
+            (TokenKind::Minus, Expr::Number(n)) => Ok(Expr::Number(Token {
+                position: op.position,
+                kind: integer_token_kind(-n.get_integer().unwrap()),
+            })),
+            (TokenKind::Minus, Expr::FloatNumber(f)) => Ok(Expr::FloatNumber(Token {
+                position: op.position,
+                kind: TokenKind::FloatNumber(-NotNan::new(f.get_float().unwrap()).unwrap()),
+            })),
+            (TokenKind::Plus, Expr::Number(_) | Expr::FloatNumber(_)) => Ok(arg),
+            _ => Ok(Expr::Unary(op, Box::new(arg))),
+        }
+    }
+
     fn visit_binary_expr(
         &mut self,
         op: Token,
@@ -30,11 +56,13 @@ impl Rewriter<String> for Folder {
 
         let res: Expr = match (&left, &right) {
             (Expr::Number(a), Expr::Number(b)) => {
-                let na = a.get_number().unwrap();
-                let nb = b.get_number().unwrap();
+                //fold over arbitrary-precision integers, so overflow promotes to bignum
+                //like it does at runtime
+                let na = a.get_integer().unwrap();
+                let nb = b.get_integer().unwrap();
 
                 enum FoldResult {
-                    Ok(i64),
+                    Ok(BigInt),
                     OkBool(bool),
                     Warning(String),
                     Error(String),
@@ -43,18 +71,35 @@ impl Rewriter<String> for Folder {
                 let result = match &op.kind {
                     TokenKind::Star => FoldResult::Ok(na*nb),
 
-                    TokenKind::Slash => match na.checked_div(nb) {
+                    TokenKind::Slash => match na.checked_div(&nb) {
                         Some(result) => FoldResult::Ok(result),
                         None => {
                             FoldResult::Warning(format!("encountered zero division while folding constants, assuming it is intended [{}]", op.position))
                         }
                     },
 
-                    TokenKind::Mod => match na.checked_rem(nb) {
-                        Some(result) => FoldResult::Ok(result),
-                        None => {
-                            FoldResult::Warning( format!("encountered modulo 0 while folding constants, assuming it is intended [{}]", op.position))
-                        }
+                    TokenKind::Mod => if nb.is_zero() {
+                        FoldResult::Warning( format!("encountered modulo 0 while folding constants, assuming it is intended [{}]", op.position))
+                    } else {
+                        FoldResult::Ok(na % nb)
+                    },
+
+                    TokenKind::FloorDiv => if nb.is_zero() {
+                        FoldResult::Warning(format!("encountered zero division while folding constants, assuming it is intended [{}]", op.position))
+                    } else {
+                        FoldResult::Ok(Integer::div_floor(&na, &nb))
+                    },
+
+                    TokenKind::BitAnd => FoldResult::Ok(na & nb),
+                    TokenKind::BitOr => FoldResult::Ok(na | nb),
+                    TokenKind::BitXor => FoldResult::Ok(na ^ nb),
+
+                    //shifts that are too large are left to runtime, which reports them
+                    TokenKind::ShiftLeft | TokenKind::ShiftRight => match nb.to_usize() {
+                        Some(shift) if op.kind == TokenKind::ShiftRight => FoldResult::Ok(na >> shift),
+                        Some(shift) if shift_fits(na.bits(), shift as u64) => FoldResult::Ok(na << shift),
+                        Some(_) => FoldResult::Warning(format!("shift count is too large at [{}]", op.position)),
+                        None => FoldResult::Warning(format!("unsupported shift count when folding constants at [{}]", op.position))
                     },
 
                     TokenKind::Plus => FoldResult::Ok(na + nb),
@@ -64,9 +109,13 @@ impl Rewriter<String> for Folder {
                     TokenKind::CompareEquals => FoldResult::OkBool(na==nb),
                     TokenKind::CompareNotEquals => FoldResult::OkBool(na!=nb),
 
-                    TokenKind::Power => match na.checked_pow(nb as u64 as u32) {
-                        Some(value) => FoldResult::Ok(value),
-                        None => FoldResult::Warning(format!("overflow when folding power at [{}]", op.position))
+                    //powers that are too large are left to runtime, which reports them
+                    TokenKind::Power => match nb.to_u64() {
+                        Some(exponent) => match checked_pow(&na, exponent) {
+                            Some(power) => FoldResult::Ok(power),
+                            None => FoldResult::Warning(format!("exponent is too large at [{}]", op.position)),
+                        },
+                        None => FoldResult::Warning(format!("unsupported exponent when folding power at [{}]", op.position))
                     },
 
                     _any_other => FoldResult::Error(format!("unexpected binary operator {}", _any_other)),
@@ -75,7 +124,7 @@ impl Rewriter<String> for Folder {
                 match result {
                     FoldResult::Ok(number) => Expr::Number(Token {
                         position: a.position,
-                        kind: TokenKind::Number(number),
+                        kind: integer_token_kind(number),
                     }),
                     FoldResult::OkBool(b) => Expr::Bool(Token {
                         position: a.position,
@@ -105,3 +154,51 @@ impl Rewriter<String> for Folder {
         Ok(res)
     }
 }
+
+fn integer_token_kind(n: BigInt) -> TokenKind {
+    match n.to_i64() {
+        Some(small) => TokenKind::Number(small),
+        None => TokenKind::BigNumber(n),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::Folder;
+    use crate::parsing::ast::{Expr, Stmt};
+    use crate::parsing::lexer::{tokenize, TokenKind};
+    use crate::parsing::parser::program_parser;
+
+    fn fold_expression(source: &str) -> Expr {
+        let tokens = tokenize(source).unwrap();
+        let tokens = tokens.iter().collect::<Vec<_>>();
+        let program = program_parser::program(tokens.as_slice()).unwrap();
+        match Folder::fold_constants(program).unwrap().pop() {
+            Some(Stmt::Expression(expr)) => expr,
+            other => panic!("expected expression, got {:?}", other),
+        }
+    }
+
+    #[rstest]
+    #[case("-5", TokenKind::Number(-5))]
+    #[case("+5", TokenKind::Number(5))]
+    #[case("-(2 * 3)", TokenKind::Number(-6))]
+    #[case("-9223372036854775808", TokenKind::Number(i64::MIN))]
+    #[case("-2 ** 2", TokenKind::Number(-4))]
+    fn negative_literals_should_be_folded(#[case] source: &str, #[case] expected: TokenKind) {
+        match fold_expression(source) {
+            Expr::Number(token) => assert_eq!(token.kind, expected),
+            other => panic!("{} was not folded: {:?}", source, other),
+        }
+    }
+
+    #[test]
+    fn negative_floats_should_be_folded() {
+        match fold_expression("-1.5") {
+            Expr::FloatNumber(token) => assert_eq!(token.get_float(), Some(-1.5)),
+            other => panic!("-1.5 was not folded: {:?}", other),
+        }
+    }
+}
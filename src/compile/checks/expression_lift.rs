@@ -1,7 +1,7 @@
 use crate::compile::checks::tree_rewriter::Rewriter;
+use crate::parsing::ast::Expr;
 use crate::parsing::ast::{Program, Stmt};
 use crate::parsing::lexer::Token;
-use crate::Expr;
 
 pub struct ExpressionLifter {}
 
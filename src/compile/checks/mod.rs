@@ -12,7 +12,7 @@ use crate::compile::checks::variable_annotation_generation::AnnotationGenerator;
 use crate::parsing::ast::Program;
 use crate::parsing::lexer::Token;
 use indexmap::{IndexMap, IndexSet};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum VariableType {
@@ -27,6 +27,8 @@ pub struct Annotations {
     /// variables declared inside blocks with corresponding type (boxed or normal)
     block_symbol_table: HashMap<Token, IndexMap<String, VariableType>>,
     closed_names_table: HashMap<Token, IndexSet<String>>,
+    /// functions (identified by name or arrow token) that contain `yield`
+    generator_functions: HashSet<Token>,
 }
 
 impl Annotations {
@@ -60,6 +62,14 @@ impl Annotations {
     pub fn get_closure_scope(&self, closure_id: &Token) -> Option<&IndexSet<String>> {
         self.closed_names_table.get(closure_id)
     }
+
+    pub fn mark_generator(&mut self, function_id: &Token) {
+        self.generator_functions.insert(function_id.clone());
+    }
+
+    pub fn is_generator(&self, function_id: &Token) -> bool {
+        self.generator_functions.contains(function_id)
+    }
 }
 
 pub fn check_optimize(tree: Program) -> Result<(Program, Annotations), String> {
@@ -1,7 +1,7 @@
 use crate::compile::checks::tree_visitor::Visitor;
+use crate::parsing::ast::Expr;
 use crate::parsing::ast::{EnumVariant, Program, Stmt};
 use crate::parsing::lexer::Token;
-use crate::Expr;
 use std::collections::HashMap;
 
 /// checks that names and arguments do not repeat in same scope
@@ -73,7 +73,7 @@ impl Visitor<String> for NameRedefinitionChecker {
         })?;
 
         self.new_scope();
-        for arg_name in args.iter().chain(vararg.into_iter()) {
+        for arg_name in args.iter().chain(vararg) {
             self.declare_name(arg_name).map_err(|_e| {
                 format!(
                     "argument {} repeats in function {} at [{}]",
@@ -102,6 +102,21 @@ impl Visitor<String> for NameRedefinitionChecker {
         Ok(())
     }
 
+    fn visit_for_stmt(
+        &mut self,
+        _keyword: &Token,
+        variable: &Token,
+        iterable: &Expr,
+        body: &Expr,
+    ) -> Result<(), String> {
+        self.visit_expr(iterable)?;
+        self.new_scope();
+        let _ = self.declare_name(variable);
+        self.visit_expr(body)?;
+        self.pop_scope();
+        Ok(())
+    }
+
     fn visit_anon_function_expr(
         &mut self,
         args: &[Token],
@@ -111,7 +126,7 @@ impl Visitor<String> for NameRedefinitionChecker {
     ) -> Result<(), String> {
         self.new_scope();
 
-        for arg_name in args.iter().chain(vararg.into_iter()) {
+        for arg_name in args.iter().chain(vararg) {
             self.declare_name(arg_name).map_err(|_e| {
                 format!(
                     "argument {} repeats in anonymous function at [{}]",
@@ -203,20 +218,23 @@ impl Visitor<String> for NameRedefinitionChecker {
 
     fn visit_import_stmt(
         &mut self,
+        _level: usize,
         _module: &[Token],
-        name: &Token,
-        rename: Option<&Token>,
+        names: &[(Token, Option<Token>)],
     ) -> Result<(), String> {
-        let import_name = rename.unwrap_or(name);
+        for (name, rename) in names {
+            let import_name = rename.as_ref().unwrap_or(name);
 
-        self.declare_name(import_name).map_err(|e| {
-            format!(
-                "name {} [{}] is redefined in block, previous definition at [{}]",
-                import_name.get_string().unwrap(),
-                import_name.position,
-                e.position
-            )
-        })
+            self.declare_name(import_name).map_err(|e| {
+                format!(
+                    "name {} [{}] is redefined in block, previous definition at [{}]",
+                    import_name.get_string().unwrap(),
+                    import_name.position,
+                    e.position
+                )
+            })?;
+        }
+        Ok(())
     }
 
     fn visit_method(
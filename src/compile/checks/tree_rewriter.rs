@@ -1,8 +1,8 @@
 #![allow(clippy::boxed_local)]
 
+use crate::parsing::ast::Expr;
 use crate::parsing::ast::{EnumVariant, Stmt};
 use crate::parsing::lexer::Token;
-use crate::Expr;
 
 pub(super) trait Rewriter<E> {
     fn visit_stmt(&mut self, stmt: Stmt) -> Result<Stmt, E> {
@@ -11,6 +11,7 @@ pub(super) trait Rewriter<E> {
             Stmt::Assignment(target, value) => self.visit_assignment_stmt(target, value),
             Stmt::Expression(e) => self.visit_expr_stmt(e),
             Stmt::Assert(keyword, value) => self.visit_assert_statement(keyword, value),
+            Stmt::Yield(keyword, value) => self.visit_yield_stmt(keyword, value),
             Stmt::Pass(keyword) => self.visit_pass_stmt(keyword),
             Stmt::FunctionDeclaration {
                 name,
@@ -34,20 +35,30 @@ pub(super) trait Rewriter<E> {
                 implementations,
             } => self.visit_impl_block(name, implementations),
             Stmt::Import {
+                level,
                 module,
-                name,
-                rename,
-            } => self.visit_import_stmt(module, name, rename),
+                names,
+            } => self.visit_import_stmt(level, module, names),
+            Stmt::ImportAll {
+                level,
+                module,
+                star,
+            } => self.visit_import_all_stmt(level, module, star),
+            Stmt::For {
+                keyword,
+                variable,
+                iterable,
+                body,
+            } => self.visit_for_stmt(keyword, variable, iterable, body),
         }
     }
 
     fn visit_var_stmt(&mut self, name: Token, rhs: Option<Expr>) -> Result<Stmt, E> {
         Ok(Stmt::VarDeclaration(
             name,
-            if rhs.is_some() {
-                Some(self.visit_expr(rhs.unwrap())?)
-            } else {
-                None
+            match rhs {
+                Some(rhs) => Some(self.visit_expr(rhs)?),
+                None => None,
             },
         ))
     }
@@ -64,6 +75,10 @@ pub(super) trait Rewriter<E> {
         Ok(Stmt::Assert(keyword, self.visit_expr(expr)?))
     }
 
+    fn visit_yield_stmt(&mut self, keyword: Token, expr: Expr) -> Result<Stmt, E> {
+        Ok(Stmt::Yield(keyword, self.visit_expr(expr)?))
+    }
+
     fn visit_pass_stmt(&mut self, keyword: Token) -> Result<Stmt, E> {
         Ok(Stmt::Pass(keyword))
     }
@@ -120,14 +135,42 @@ pub(super) trait Rewriter<E> {
 
     fn visit_import_stmt(
         &mut self,
+        level: usize,
         module: Vec<Token>,
-        name: Token,
-        rename: Option<Token>,
+        names: Vec<(Token, Option<Token>)>,
     ) -> Result<Stmt, E> {
         Ok(Stmt::Import {
+            level,
             module,
-            name,
-            rename,
+            names,
+        })
+    }
+
+    fn visit_import_all_stmt(
+        &mut self,
+        level: usize,
+        module: Vec<Token>,
+        star: Token,
+    ) -> Result<Stmt, E> {
+        Ok(Stmt::ImportAll {
+            level,
+            module,
+            star,
+        })
+    }
+
+    fn visit_for_stmt(
+        &mut self,
+        keyword: Token,
+        variable: Token,
+        iterable: Expr,
+        body: Expr,
+    ) -> Result<Stmt, E> {
+        Ok(Stmt::For {
+            keyword,
+            variable,
+            iterable: self.visit_expr(iterable)?,
+            body: self.visit_expr(body)?,
         })
     }
 
@@ -138,6 +181,7 @@ pub(super) trait Rewriter<E> {
             Expr::FloatNumber(n) => self.visit_float_number_expr(n),
             Expr::Name(n) => self.visit_variable_expr(n),
             Expr::ConstString(s) => self.visit_string_expr(s),
+            Expr::Interpolation(start, parts) => self.visit_interpolation_expr(start, parts),
             Expr::Binary(op, a, b) => self.visit_binary_expr(op, a, b),
             Expr::Unary(op, a) => self.visit_unary_expr(op, a),
             Expr::If(cond, then_branch, else_branch) => {
@@ -175,6 +219,14 @@ pub(super) trait Rewriter<E> {
         Ok(Expr::ConstString(string_literal))
     }
 
+    fn visit_interpolation_expr(&mut self, start: Token, parts: Vec<Expr>) -> Result<Expr, E> {
+        let parts = parts
+            .into_iter()
+            .map(|part| self.visit_expr(part))
+            .collect::<Result<Vec<Expr>, E>>()?;
+        Ok(Expr::Interpolation(start, parts))
+    }
+
     fn visit_binary_expr(
         &mut self,
         op: Token,
@@ -198,10 +250,9 @@ pub(super) trait Rewriter<E> {
     ) -> Result<Expr, E> {
         let condition = Box::new(self.visit_expr(*condition)?);
         let then_branch = Box::new(self.visit_expr(*then_branch)?);
-        let else_branch = if else_branch.is_some() {
-            Some(Box::new(self.visit_expr(*else_branch.unwrap())?))
-        } else {
-            None
+        let else_branch = match else_branch {
+            Some(else_branch) => Some(Box::new(self.visit_expr(*else_branch)?)),
+            None => None,
         };
 
         Ok(Expr::If(condition, then_branch, else_branch))
@@ -242,10 +293,9 @@ pub(super) trait Rewriter<E> {
         let target = Box::new(self.visit_expr(*target)?);
         let mut processed_args = vec![];
         for arg in args {
-            processed_args.push(if arg.is_some() {
-                Some(self.visit_expr(arg.unwrap())?)
-            } else {
-                None
+            processed_args.push(match arg {
+                Some(arg) => Some(self.visit_expr(arg)?),
+                None => None,
             });
         }
         Ok(Expr::PartialCall(target, processed_args))
@@ -1,6 +1,6 @@
+use crate::parsing::ast::Expr;
 use crate::parsing::ast::{EnumVariant, Stmt};
 use crate::parsing::lexer::Token;
-use crate::Expr;
 
 #[allow(unused)]
 pub(super) trait Visitor<E> {
@@ -10,6 +10,7 @@ pub(super) trait Visitor<E> {
             Stmt::Assignment(target, value) => self.visit_assignment_stmt(target, value),
             Stmt::Expression(e) => self.visit_expr_stmt(e),
             Stmt::Assert(keyword, value) => self.visit_assert_statement(keyword, value),
+            Stmt::Yield(keyword, value) => self.visit_yield_stmt(keyword, value),
             Stmt::Pass(keyword) => self.visit_pass_stmt(keyword),
             Stmt::FunctionDeclaration {
                 name,
@@ -32,16 +33,27 @@ pub(super) trait Visitor<E> {
                 implementations,
             } => self.visit_impl_block(name, implementations),
             Stmt::Import {
+                level,
                 module,
-                name,
-                rename,
-            } => self.visit_import_stmt(module, name, rename.as_ref()),
+                names,
+            } => self.visit_import_stmt(*level, module, names),
+            Stmt::ImportAll {
+                level,
+                module,
+                star,
+            } => self.visit_import_all_stmt(*level, module, star),
+            Stmt::For {
+                keyword,
+                variable,
+                iterable,
+                body,
+            } => self.visit_for_stmt(keyword, variable, iterable, body),
         }
     }
 
     fn visit_var_stmt(&mut self, _variable_name: &Token, rhs: Option<&Expr>) -> Result<(), E> {
-        if rhs.is_some() {
-            self.visit_expr(rhs.unwrap())?;
+        if let Some(rhs) = rhs {
+            self.visit_expr(rhs)?;
         }
         Ok(())
     }
@@ -58,6 +70,10 @@ pub(super) trait Visitor<E> {
         self.visit_expr(expr)
     }
 
+    fn visit_yield_stmt(&mut self, _keyword: &Token, expr: &Expr) -> Result<(), E> {
+        self.visit_expr(expr)
+    }
+
     fn visit_pass_stmt(&mut self, _keyword: &Token) -> Result<(), E> {
         Ok(())
     }
@@ -109,13 +125,33 @@ pub(super) trait Visitor<E> {
 
     fn visit_import_stmt(
         &mut self,
+        level: usize,
         module: &[Token],
-        name: &Token,
-        rename: Option<&Token>,
+        names: &[(Token, Option<Token>)],
     ) -> Result<(), E> {
         Ok(())
     }
 
+    fn visit_import_all_stmt(
+        &mut self,
+        level: usize,
+        module: &[Token],
+        star: &Token,
+    ) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_for_stmt(
+        &mut self,
+        keyword: &Token,
+        variable: &Token,
+        iterable: &Expr,
+        body: &Expr,
+    ) -> Result<(), E> {
+        self.visit_expr(iterable)?;
+        self.visit_expr(body)
+    }
+
     fn visit_expr(&mut self, expr: &Expr) -> Result<(), E> {
         match expr {
             Expr::Number(n) => self.visit_number_expr(n),
@@ -123,6 +159,7 @@ pub(super) trait Visitor<E> {
             Expr::Bool(b) => self.visit_bool_expr(b),
             Expr::Name(n) => self.visit_variable_expr(n),
             Expr::ConstString(s) => self.visit_string_expr(s),
+            Expr::Interpolation(start, parts) => self.visit_interpolation_expr(start, parts),
             Expr::Binary(op, a, b) => self.visit_binary_expr(op, a, b),
             Expr::Unary(op, a) => self.visit_unary_expr(op, a),
             Expr::If(cond, then_branch, else_branch) => {
@@ -161,6 +198,10 @@ pub(super) trait Visitor<E> {
         Ok(())
     }
 
+    fn visit_interpolation_expr(&mut self, start: &Token, parts: &[Expr]) -> Result<(), E> {
+        parts.iter().try_for_each(|part| self.visit_expr(part))
+    }
+
     fn visit_binary_expr(&mut self, op: &Token, left: &Expr, right: &Expr) -> Result<(), E> {
         self.visit_expr(left)?;
         self.visit_expr(right)
@@ -178,8 +219,8 @@ pub(super) trait Visitor<E> {
     ) -> Result<(), E> {
         self.visit_expr(condition)?;
         self.visit_expr(then_branch)?;
-        if else_branch.is_some() {
-            self.visit_expr(else_branch.unwrap())?;
+        if let Some(else_branch) = else_branch {
+            self.visit_expr(else_branch)?;
         }
         Ok(())
     }
@@ -1,8 +1,8 @@
 use crate::compile::checks::tree_visitor::Visitor;
 use crate::compile::checks::{Annotations, VariableType};
+use crate::parsing::ast::Expr;
 use crate::parsing::ast::{Program, Stmt};
 use crate::parsing::lexer::Token;
-use crate::Expr;
 use std::collections::HashMap;
 
 pub struct AnnotationGenerator<'a> {
@@ -150,7 +150,7 @@ impl<'a> Visitor<String> for AnnotationGenerator<'a> {
     ) -> Result<(), String> {
         self.new_scope(ScopeType::Function, name);
         self.annotations.get_or_create_closure_scope(name);
-        for arg_name in args.iter().chain(vararg.into_iter()) {
+        for arg_name in args.iter().chain(vararg) {
             self.declare_name(arg_name);
             self.define_name(arg_name);
         }
@@ -193,9 +193,10 @@ impl<'a> Visitor<String> for AnnotationGenerator<'a> {
                     self.declare_name(name);
                 }
 
-                Stmt::Import { name, rename, .. } => {
-                    let import_name = rename.as_ref().unwrap_or(name);
-                    self.declare_name(import_name);
+                Stmt::Import { names, .. } => {
+                    for (name, rename) in names {
+                        self.declare_name(rename.as_ref().unwrap_or(name));
+                    }
                 }
 
                 _ => {}
@@ -219,7 +220,7 @@ impl<'a> Visitor<String> for AnnotationGenerator<'a> {
     ) -> Result<(), String> {
         self.new_scope(ScopeType::Function, arrow);
         self.annotations.get_or_create_closure_scope(arrow);
-        for arg_name in args.iter().chain(vararg.into_iter()) {
+        for arg_name in args.iter().chain(vararg) {
             self.declare_name(arg_name);
             self.define_name(arg_name);
         }
@@ -229,6 +230,39 @@ impl<'a> Visitor<String> for AnnotationGenerator<'a> {
         Ok(())
     }
 
+    fn visit_yield_stmt(&mut self, keyword: &Token, expr: &Expr) -> Result<(), String> {
+        self.visit_expr(expr)?;
+
+        let function = self
+            .scopes
+            .iter()
+            .rev()
+            .find(|(scope_type, ..)| *scope_type == ScopeType::Function)
+            .map(|(_, function, _)| function.clone())
+            .ok_or_else(|| format!("yield outside of function [{}]", keyword.position))?;
+        self.annotations.mark_generator(&function);
+        Ok(())
+    }
+
+    fn visit_for_stmt(
+        &mut self,
+        keyword: &Token,
+        variable: &Token,
+        iterable: &Expr,
+        body: &Expr,
+    ) -> Result<(), String> {
+        self.visit_expr(iterable)?;
+
+        //loop variable lives in its own scope, so it is boxed if captured by closures
+        self.new_scope(ScopeType::Block, keyword);
+        self.annotations.get_or_create_block_scope(keyword);
+        self.declare_name(variable);
+        self.define_name(variable);
+        self.visit_expr(body)?;
+        self.pop_scope();
+        Ok(())
+    }
+
     fn visit_impl_block(&mut self, name: &Token, implementations: &[Stmt]) -> Result<(), String> {
         self.lookup_name(name.get_string().unwrap());
 
@@ -241,11 +275,13 @@ impl<'a> Visitor<String> for AnnotationGenerator<'a> {
 
     fn visit_import_stmt(
         &mut self,
+        _level: usize,
         _module: &[Token],
-        name: &Token,
-        rename: Option<&Token>,
+        names: &[(Token, Option<Token>)],
     ) -> Result<(), String> {
-        self.define_name(rename.unwrap_or(name));
+        for (name, rename) in names {
+            self.define_name(rename.as_ref().unwrap_or(name));
+        }
         Ok(())
     }
 }
@@ -90,9 +90,11 @@ impl<'gc, 'annotations, 'chunk> Compiler<'gc, 'annotations, 'chunk> {
                     compiler.declare_local(name.get_string().unwrap(), VariableType::Global);
                 }
 
-                Stmt::Import { name, rename, .. } => {
-                    let name = rename.as_ref().unwrap_or(name);
-                    compiler.declare_local(name.get_string().unwrap(), VariableType::Global);
+                Stmt::Import { names, .. } => {
+                    for (name, rename) in names {
+                        let name = rename.as_ref().unwrap_or(name);
+                        compiler.declare_local(name.get_string().unwrap(), VariableType::Global);
+                    }
                 }
 
                 _ => {}
@@ -202,6 +204,38 @@ impl<'gc, 'annotations, 'chunk> Compiler<'gc, 'annotations, 'chunk> {
         self.current_chunk.import_names.len() - 1
     }
 
+    /// module path of import, relative one (`level` leading dots) starts at ancestor of module
+    /// being compiled: `.b` in `a.x` is `a.b`, `..b` is an error as it escapes package `a`
+    fn import_path(
+        &self,
+        level: usize,
+        module: &[Token],
+        position: &Token,
+    ) -> Result<Vec<String>, String> {
+        let mut path = match level {
+            0 => vec![],
+            _ => {
+                let current = &self.current_chunk.module;
+                let base = current.ancestor(level).ok_or_else(|| {
+                    format!(
+                        "relative import {}{} escapes package of module {} [{}]",
+                        ".".repeat(level),
+                        module
+                            .iter()
+                            .map(|t| t.get_string().unwrap())
+                            .collect::<Vec<_>>()
+                            .join("."),
+                        current,
+                        position.position
+                    )
+                })?;
+                base.parts().to_vec()
+            }
+        };
+        path.extend(module.iter().map(|t| t.get_string().unwrap().to_string()));
+        Ok(path)
+    }
+
     /// looks up variable by name, considering only well-defined variables
     /// (i.e. previously declared with var, def, struct or enum)
     fn lookup_local(&self, name: &str) -> Option<(VariableType, usize)> {
@@ -305,6 +339,7 @@ impl<'gc, 'annotations, 'chunk> Compiler<'gc, 'annotations, 'chunk> {
         };
 
         let mut chunk = Chunk::new(name.clone(), self.current_chunk.module.clone(), arity);
+        chunk.is_generator = self.annotations.is_generator(name);
 
         let mut inner_compiler =
             Compiler::new(self.annotations, self.gc, name.clone(), arity, &mut chunk);
@@ -328,7 +363,7 @@ impl<'gc, 'annotations, 'chunk> Compiler<'gc, 'annotations, 'chunk> {
         //define function inside itself
 
         inner_compiler.new_scope();
-        for arg_name in args.iter().chain(vararg.into_iter()) {
+        for arg_name in args.iter().chain(vararg) {
             match inner_compiler.declare_local(arg_name.get_string().unwrap(), VariableType::Normal)
             {
                 Some(_) => {
@@ -729,6 +764,7 @@ impl<'gc, 'annotations, 'chunk> Compiler<'gc, 'annotations, 'chunk> {
                     //otherwise, just put it in global name
                     let idx = self.get_or_create_name(varname);
                     result.push(Opcode::StoreGLobal(idx as u16), target.position.0);
+                    self.dec_stack_height();
                 }
                 //in case we need some result value
                 if self.needs_value() {
@@ -761,6 +797,7 @@ impl<'gc, 'annotations, 'chunk> Compiler<'gc, 'annotations, 'chunk> {
                         },
                         property.position.0,
                     );
+                    self.sub_stack_height(2); //pointer and value are consumed
 
                     if self.needs_value() {
                         result.push(Opcode::LoadNothing, property.position.0);
@@ -781,6 +818,19 @@ impl<'gc, 'annotations, 'chunk> Compiler<'gc, 'annotations, 'chunk> {
                 self.pop_requirement();
                 result.append(body);
                 result.push(Opcode::Assert, token.position.0);
+                self.dec_stack_height(); //asserted value is consumed
+                if self.needs_value() {
+                    result.push(Opcode::LoadNothing, token.position.0);
+                }
+            }
+
+            Stmt::Yield(token, expr) => {
+                self.require_value();
+                let body = self.visit_expr(expr)?;
+                self.pop_requirement();
+                result.append(body);
+                result.push(Opcode::Yield, token.position.0);
+                self.dec_stack_height(); //yielded value is moved out of frame
                 if self.needs_value() {
                     result.push(Opcode::LoadNothing, token.position.0);
                 }
@@ -831,32 +881,144 @@ impl<'gc, 'annotations, 'chunk> Compiler<'gc, 'annotations, 'chunk> {
             }
 
             Stmt::Import {
+                level,
                 module,
-                name,
-                rename,
+                names,
             } => {
-                let path = module
-                    .iter()
-                    .map(|t| t.get_string().unwrap().to_string())
-                    .collect::<Vec<_>>();
-                let module = Module::new(path);
+                let path = self.import_path(*level, module, &names[0].0)?;
+
+                for (name, rename) in names {
+                    let name_string = name.get_string().unwrap().to_string();
+                    let opcode = if path.is_empty() {
+                        //`import a` binds the whole module
+                        let idx = self.get_or_create_import_name((
+                            Module::new(vec![name_string]),
+                            String::new(),
+                        ));
+                        Opcode::ImportModule(idx as u16)
+                    } else {
+                        let idx = self
+                            .get_or_create_import_name((Module::new(path.clone()), name_string));
+                        Opcode::Import(idx as u16)
+                    };
 
-                let idx = self
-                    .get_or_create_import_name((module, name.get_string().unwrap().to_string()));
-                let import = |slf: &mut Compiler| {
-                    let mut importname = AnnotatedCodeBlob::new();
+                    let import = |slf: &mut Compiler| {
+                        let mut importname = AnnotatedCodeBlob::new();
 
-                    importname.push(Opcode::Import(idx as u16), name.position.0); //code block
+                        importname.push(opcode, name.position.0); //code block
 
-                    slf.inc_stack_height();
+                        slf.inc_stack_height();
 
-                    Ok(importname)
-                };
+                        Ok(importname)
+                    };
 
-                result.append(self.create_named_entity(rename.as_ref().unwrap_or(name), &import)?);
+                    result.append(
+                        self.create_named_entity(rename.as_ref().unwrap_or(name), &import)?,
+                    );
+                }
 
                 if self.needs_value() {
-                    result.push(Opcode::LoadNothing, name.position.0);
+                    result.push(Opcode::LoadNothing, names[0].0.position.0);
+                }
+            }
+
+            Stmt::ImportAll {
+                level,
+                module,
+                star,
+            } => {
+                if self.function_context.name != *SCRIPT_TOKEN || self.names.len() != 1 {
+                    return Err(format!(
+                        "wildcard import is only allowed at module level [{}]",
+                        star.position
+                    ));
+                }
+
+                let path = self.import_path(*level, module, star)?;
+                let idx = self.get_or_create_import_name((Module::new(path), String::new()));
+                result.push(Opcode::ImportAll(idx as u16), star.position.0);
+
+                if self.needs_value() {
+                    result.push(Opcode::LoadNothing, star.position.0);
+                }
+            }
+
+            Stmt::For {
+                keyword,
+                variable,
+                iterable,
+                body,
+            } => {
+                /*
+                evaluation scheme:
+                    eval(iterable)
+                    GetIter
+                loop_start:
+                    ForIter end_loop //pushes loop variable
+                    (box loop variable if it is captured)
+                    body(return=false)
+                    pop(n) //loop variable and locals created by body
+                    JumpBack loop_start
+                end_loop:
+                 */
+                let line = keyword.position.0;
+
+                self.require_value();
+                result.append(self.visit_expr(iterable)?);
+                self.pop_requirement();
+                result.push(Opcode::GetIter, line);
+
+                //iterator stays on stack during the whole loop
+                self.new_scope();
+                self.dec_stack_height();
+                let iterator_name = "`iterator`";
+                self.declare_local(iterator_name, VariableType::Normal);
+                self.define_local(iterator_name);
+
+                let mut loop_body = AnnotatedCodeBlob::new();
+                self.new_scope();
+
+                let variable_name = variable.get_string().unwrap();
+                let is_boxed = matches!(
+                    self.annotations
+                        .get_block_scope(keyword)
+                        .and_then(|scope| scope.get(variable_name)),
+                    Some(VariableType::Boxed)
+                );
+
+                if is_boxed {
+                    //fresh box on every iteration, so closures capture current item
+                    let item_name = "`item`";
+                    let (_, item_idx) =
+                        self.declare_local(item_name, VariableType::Normal).unwrap();
+                    self.define_local(item_name);
+                    loop_body += (Opcode::NewBox, line);
+                    loop_body += (Opcode::Duplicate, line);
+                    loop_body += (Opcode::LoadLocal(item_idx as u16), line);
+                    loop_body += (Opcode::StoreBox, line);
+                    self.declare_local(variable_name, VariableType::Boxed);
+                } else {
+                    self.declare_local(variable_name, VariableType::Normal);
+                }
+                self.define_local(variable_name);
+
+                self.require_nothing();
+                loop_body.append(self.visit_expr(body)?);
+                self.pop_requirement();
+
+                let loop_variables = self.pop_scope();
+                loop_body += (Opcode::Pop(loop_variables as u16), line);
+
+                let body_size = loop_body.code.len();
+                result.push(Opcode::ForIter((body_size + 2) as u16), line);
+                result.append(loop_body);
+                result.push(Opcode::JumpBack((body_size + 1) as u16), line);
+
+                //exhausted iterator is popped by ForIter
+                self.pop_scope();
+
+                if self.needs_value() {
+                    result.push(Opcode::LoadNothing, line);
                 }
             }
         }
@@ -891,13 +1053,19 @@ impl<'gc, 'annotations, 'chunk> Compiler<'gc, 'annotations, 'chunk> {
             }
 
             Expr::Number(token) => {
-                let n = token.get_number().unwrap();
-                if n >= (i16::MIN as i64) && n <= (i16::MAX as i64) {
-                    result += (Opcode::LoadImmediateInt(n as i16), token.position.0);
-                } else {
-                    let constant_index = self.get_or_create_constant(Value::Int(n));
+                if let TokenKind::BigNumber(n) = &token.kind {
+                    let big = self.gc.store(n.clone());
+                    let constant_index = self.get_or_create_constant(big);
                     result += (Opcode::LoadConst(constant_index as u16), token.position.0);
-                    //TODO extension
+                } else {
+                    let n = token.get_number().unwrap();
+                    if n >= (i16::MIN as i64) && n <= (i16::MAX as i64) {
+                        result += (Opcode::LoadImmediateInt(n as i16), token.position.0);
+                    } else {
+                        let constant_index = self.get_or_create_constant(Value::Int(n));
+                        result += (Opcode::LoadConst(constant_index as u16), token.position.0);
+                        //TODO extension
+                    }
                 }
                 if !self.needs_value() {
                     result += (Opcode::Pop(1), token.position.0);
@@ -912,6 +1080,37 @@ impl<'gc, 'annotations, 'chunk> Compiler<'gc, 'annotations, 'chunk> {
                 }
             }
 
+            Expr::Interpolation(start, parts) => {
+                if parts.is_empty() {
+                    let obj_ptr = self.gc.new_interned_string("");
+                    let constant_index = self.get_or_create_constant(obj_ptr);
+                    result.push(Opcode::LoadConst(constant_index as u16), start.position.0);
+                } else {
+                    //f"a{x}b" is compiled as "a" + format(x) + "b", so that result of first
+                    //concatenation is reused by the following ones
+                    for (idx, part) in parts.iter().enumerate() {
+                        self.require_value();
+                        let part_code = self.visit_expr(part)?;
+                        self.pop_requirement();
+                        result.append(part_code);
+
+                        if !matches!(part, Expr::ConstString(..)) {
+                            result.push(Opcode::FormatString, start.position.0);
+                        }
+
+                        if idx > 0 {
+                            result.push(Opcode::Add, start.position.0);
+                            self.dec_stack_height();
+                        }
+                    }
+                    self.dec_stack_height(); // stack height is increased in outer code
+                }
+
+                if !self.needs_value() {
+                    result.push(Opcode::Pop(1), start.position.0);
+                }
+            }
+
             Expr::Unary(op, a) => {
                 self.require_value();
                 let expr = self.visit_expr(a)?;
@@ -919,15 +1118,17 @@ impl<'gc, 'annotations, 'chunk> Compiler<'gc, 'annotations, 'chunk> {
 
                 result.append(expr);
 
-                result.push(
-                    match &op.kind {
-                        TokenKind::Not => Opcode::LogicalNot,
-                        other => {
-                            panic!("unimplemented unary operator {} [{}]", other, op.position)
-                        }
-                    },
-                    op.position.0,
-                );
+                match &op.kind {
+                    TokenKind::Not => result.push(Opcode::LogicalNot, op.position.0),
+                    TokenKind::BitNot => result.push(Opcode::BitNot, op.position.0),
+                    TokenKind::Minus => result.push(Opcode::Negate, op.position.0),
+                    //unary plus leaves operand as is
+                    TokenKind::Plus => {}
+                    other => {
+                        panic!("unimplemented unary operator {} [{}]", other, op.position)
+                    }
+                }
+                self.dec_stack_height(); // stack height is increased in outer code
 
                 if !self.needs_value() {
                     result.push(Opcode::Pop(1), op.position.0);
@@ -1020,6 +1221,12 @@ impl<'gc, 'annotations, 'chunk> Compiler<'gc, 'annotations, 'chunk> {
                             TokenKind::CompareLessEqual => Opcode::TestLessEqual,
                             TokenKind::Mod => Opcode::Mod,
                             TokenKind::Power => Opcode::Power,
+                            TokenKind::FloorDiv => Opcode::FloorDiv,
+                            TokenKind::BitAnd => Opcode::BitAnd,
+                            TokenKind::BitOr => Opcode::BitOr,
+                            TokenKind::BitXor => Opcode::BitXor,
+                            TokenKind::ShiftLeft => Opcode::ShiftLeft,
+                            TokenKind::ShiftRight => Opcode::ShiftRight,
                             other => {
                                 panic!("unimplemented binary operator {} [{}]", other, op.position)
                             }
@@ -1056,10 +1263,10 @@ impl<'gc, 'annotations, 'chunk> Compiler<'gc, 'annotations, 'chunk> {
                         let mut blob = AnnotatedCodeBlob::new();
                         if self.needs_value() {
                             {
-                                blob += (Opcode::LoadNothing, *condition.indices.get(0).unwrap());
+                                blob += (Opcode::LoadNothing, *condition.indices.first().unwrap());
                             }
                         } else {
-                            blob += (Opcode::Nop, *condition.indices.get(0).unwrap());
+                            blob += (Opcode::Nop, *condition.indices.first().unwrap());
                         }
                         Ok(blob)
                     })?;
@@ -1100,6 +1307,7 @@ impl<'gc, 'annotations, 'chunk> Compiler<'gc, 'annotations, 'chunk> {
                 let target_indices_copy = target.clone();
 
                 if self.function_context.name != *SCRIPT_TOKEN //we are compiling some function other that entry point 
+                    && !self.current_chunk.is_generator //calling generator creates new frame instead
                     && target.code.len() == 1 //target is 1 op (not load_* load_box)
                     && target.code.last().unwrap().eq(&Opcode::LoadLocal(0)) //we load current function
                     && self.needs_return_value()
@@ -1208,6 +1416,7 @@ impl<'gc, 'annotations, 'chunk> Compiler<'gc, 'annotations, 'chunk> {
                 );
 
                 self.sub_stack_height(args.len());
+                self.dec_stack_height(); // target is replaced by partial
 
                 if !self.needs_value() {
                     result.push(Opcode::Pop(1), result.last_index().unwrap());
@@ -1252,6 +1461,7 @@ impl<'gc, 'annotations, 'chunk> Compiler<'gc, 'annotations, 'chunk> {
                     },
                     prop.position.0,
                 );
+                self.dec_stack_height(); // target is replaced by field value
 
                 if !self.needs_value() {
                     result.push(Opcode::Pop(1), prop.position.0);
@@ -1268,6 +1478,7 @@ impl<'gc, 'annotations, 'chunk> Compiler<'gc, 'annotations, 'chunk> {
                 let idx = self.get_or_create_name(prop.get_string().unwrap());
 
                 result.push(Opcode::TestProperty(idx as u16), prop.position.0);
+                self.dec_stack_height(); // target is replaced by test result
 
                 if !self.needs_value() {
                     result.push(Opcode::Pop(1), prop.position.0);
@@ -1377,7 +1588,7 @@ mod complex_operators_stack_tests {
         data::gc::GC,
         execution::{arity::Arity, chunk::Chunk, module::Module},
         parsing::{
-            ast::Expr,
+            ast::{Expr, Stmt},
             lexer::{Index, Token, TokenKind},
         },
     };
@@ -1461,4 +1672,74 @@ mod complex_operators_stack_tests {
 
         compile_ast_with_value(gc, ast);
     }
+
+    #[rstest]
+    fn compiler_should_produce_1_value_in_unary(gc: GC) {
+        let ast = Expr::Unary(
+            Token {
+                kind: TokenKind::Not,
+                position: Index(0, 0),
+            },
+            Box::new(Expr::Number(ZERO.clone())),
+        );
+
+        compile_ast_with_value(gc, ast);
+    }
+
+    #[rstest]
+    fn compiler_should_produce_1_value_in_property_access(gc: GC) {
+        let property = Token {
+            kind: TokenKind::Name("x".to_string()),
+            position: Index(0, 0),
+        };
+        let ast = Expr::PropertyAccess(Box::new(Expr::Number(ZERO.clone())), property);
+
+        compile_ast_with_value(gc, ast);
+    }
+
+    #[rstest]
+    fn compiler_should_produce_1_value_in_partial_call(gc: GC) {
+        let ast = Expr::PartialCall(
+            Box::new(Expr::Number(ZERO.clone())),
+            vec![None, Some(Expr::Number(ZERO.clone()))],
+        );
+
+        compile_ast_with_value(gc, ast);
+    }
+
+    fn name(name: &str) -> Token {
+        Token {
+            kind: TokenKind::Name(name.to_string()),
+            position: Index(0, 0),
+        }
+    }
+
+    #[rstest]
+    fn compiler_should_produce_1_value_in_property_test(gc: GC) {
+        let ast = Expr::PropertyTest(Box::new(Expr::Number(ZERO.clone())), name("x"));
+
+        compile_ast_with_value(gc, ast);
+    }
+
+    #[rstest]
+    fn compiler_should_produce_1_value_in_global_assignment(gc: GC) {
+        let statement = Stmt::Assignment(name("x"), Expr::Number(ZERO.clone()));
+
+        compile_ast_with_value(gc, Expr::SingleStatement(Box::new(statement)));
+    }
+
+    #[rstest]
+    fn compiler_should_produce_1_value_in_property_assignment(gc: GC) {
+        let target = Expr::PropertyAccess(Box::new(Expr::Number(ZERO.clone())), name("x"));
+        let statement = Stmt::PropertyAssignment(target, Expr::Number(ZERO.clone()));
+
+        compile_ast_with_value(gc, Expr::SingleStatement(Box::new(statement)));
+    }
+
+    #[rstest]
+    fn compiler_should_produce_1_value_in_assert(gc: GC) {
+        let statement = Stmt::Assert(name("assert"), Expr::Number(ZERO.clone()));
+
+        compile_ast_with_value(gc, Expr::SingleStatement(Box::new(statement)));
+    }
 }
@@ -0,0 +1,939 @@
+//! conversion between Rust values and BLOP values via serde.
+//!
+//! Serialization produces plain data: maps, vectors, strings, numbers, bools and `Nothing`.
+//! Integers that do not fit into `Int` become bignums.
+//! Rust structs become maps keyed by field name and enums are externally tagged
+//! (`"Variant"` for unit variants, `{"Variant": content}` otherwise).
+//!
+//! Deserialization additionally accepts struct instances (read by field name) and enum variant
+//! instances (matched by variant name).
+use std::fmt::{Display, Formatter};
+
+use indexmap::IndexMap;
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess,
+    Unexpected, VariantAccess, Visitor,
+};
+use serde::ser::{self, Serialize};
+
+use super::gc::GC;
+use super::objects::{OwnedObjectItem, StackObject, VMap, VVec, Value};
+use super::value_ops::normalize_bigint;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PathSegment {
+    Index(usize),
+    Field(String),
+}
+
+/// error of conversion together with location inside converted value where it happened
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConversionError {
+    path: Vec<PathSegment>,
+    message: String,
+}
+
+impl ConversionError {
+    fn new(message: String) -> Self {
+        ConversionError {
+            path: vec![],
+            message,
+        }
+    }
+
+    fn within(mut self, segment: PathSegment) -> Self {
+        self.path.insert(0, segment);
+        self
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// location of failed item like `servers[1].port`, empty string for top-level value
+    pub fn path(&self) -> String {
+        let mut result = String::new();
+        for segment in &self.path {
+            match segment {
+                PathSegment::Index(idx) => result.push_str(&format!("[{}]", idx)),
+                PathSegment::Field(name) if result.is_empty() => result.push_str(name),
+                PathSegment::Field(name) => result.push_str(&format!(".{}", name)),
+            }
+        }
+        result
+    }
+}
+
+impl Display for ConversionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{} at `{}`", self.message, self.path())
+        }
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
+impl ser::Error for ConversionError {
+    fn custom<T: Display>(msg: T) -> Self {
+        ConversionError::new(msg.to_string())
+    }
+}
+
+impl de::Error for ConversionError {
+    fn custom<T: Display>(msg: T) -> Self {
+        ConversionError::new(msg.to_string())
+    }
+}
+
+type Result<T> = std::result::Result<T, ConversionError>;
+
+/// converts any serializable value into BLOP value allocating heap objects in `gc`
+pub fn to_value<T: Serialize + ?Sized>(value: &T, gc: &mut GC) -> Result<Value> {
+    value.serialize(ValueSerializer { gc })
+}
+
+/// converts BLOP value into any deserializable Rust value
+pub fn from_value<T: DeserializeOwned>(value: &Value) -> Result<T> {
+    T::deserialize(ValueDeserializer { value })
+}
+
+struct ValueSerializer<'gc> {
+    gc: &'gc mut GC,
+}
+
+/// integers that do not fit into `Int` are promoted to bignums
+fn int_from<T: TryInto<i64> + Into<BigInt> + Copy>(n: T, gc: &mut GC) -> Result<Value> {
+    match n.try_into() {
+        Ok(small) => Ok(Value::Int(small)),
+        Err(_) => Ok(normalize_bigint(n.into(), gc)),
+    }
+}
+
+impl<'gc> ser::Serializer for ValueSerializer<'gc> {
+    type Ok = Value;
+    type Error = ConversionError;
+
+    type SerializeSeq = VectorSerializer<'gc>;
+    type SerializeTuple = VectorSerializer<'gc>;
+    type SerializeTupleStruct = VectorSerializer<'gc>;
+    type SerializeTupleVariant = VariantSerializer<VectorSerializer<'gc>>;
+    type SerializeMap = MapSerializer<'gc>;
+    type SerializeStruct = MapSerializer<'gc>;
+    type SerializeStructVariant = VariantSerializer<MapSerializer<'gc>>;
+
+    fn serialize_bool(self, v: bool) -> Result<Value> {
+        Ok(Value::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value> {
+        int_from(v, self.gc)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value> {
+        int_from(v, self.gc)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value> {
+        int_from(v, self.gc)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value> {
+        int_from(v, self.gc)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Value> {
+        int_from(v, self.gc)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value> {
+        int_from(v, self.gc)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value> {
+        int_from(v, self.gc)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value> {
+        int_from(v, self.gc)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value> {
+        int_from(v, self.gc)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Value> {
+        int_from(v, self.gc)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value> {
+        Ok(Value::Float(v as f64))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value> {
+        Ok(Value::Float(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value> {
+        Ok(self.gc.new_string(v.encode_utf8(&mut [0; 4])))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value> {
+        Ok(self.gc.new_string(v))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value> {
+        let vector: VVec = v.iter().map(|&b| Value::Int(b as i64)).collect();
+        Ok(self.gc.store(vector))
+    }
+
+    fn serialize_none(self) -> Result<Value> {
+        Ok(Value::Nothing)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value> {
+        Ok(Value::Nothing)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value> {
+        Ok(Value::Nothing)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Value> {
+        Ok(self.gc.new_string(variant))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Value> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value> {
+        let content = value
+            .serialize(ValueSerializer { gc: self.gc })
+            .map_err(|e| e.within(PathSegment::Field(variant.to_string())))?;
+        Ok(wrap_variant(self.gc, variant, content))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Ok(VectorSerializer {
+            gc: self.gc,
+            items: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Ok(VariantSerializer {
+            variant,
+            inner: self.serialize_seq(Some(len))?,
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Ok(MapSerializer {
+            gc: self.gc,
+            map: VMap::new(),
+            next_key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Ok(VariantSerializer {
+            variant,
+            inner: self.serialize_map(Some(len))?,
+        })
+    }
+}
+
+fn wrap_variant(gc: &mut GC, variant: &str, content: Value) -> Value {
+    let mut map = VMap::new();
+    map.insert(gc.new_string(variant), content);
+    gc.store(map)
+}
+
+struct VectorSerializer<'gc> {
+    gc: &'gc mut GC,
+    items: VVec,
+}
+
+impl<'gc> VectorSerializer<'gc> {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let item = value
+            .serialize(ValueSerializer { gc: self.gc })
+            .map_err(|e| e.within(PathSegment::Index(self.items.len())))?;
+        self.items.push(item);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Value> {
+        Ok(self.gc.store(self.items))
+    }
+}
+
+impl<'gc> ser::SerializeSeq for VectorSerializer<'gc> {
+    type Ok = Value;
+    type Error = ConversionError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+impl<'gc> ser::SerializeTuple for VectorSerializer<'gc> {
+    type Ok = Value;
+    type Error = ConversionError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+impl<'gc> ser::SerializeTupleStruct for VectorSerializer<'gc> {
+    type Ok = Value;
+    type Error = ConversionError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+struct MapSerializer<'gc> {
+    gc: &'gc mut GC,
+    map: VMap,
+    next_key: Option<Value>,
+}
+
+impl<'gc> MapSerializer<'gc> {
+    fn insert<T: Serialize + ?Sized>(&mut self, key: Value, value: &T) -> Result<()> {
+        let value = value
+            .serialize(ValueSerializer { gc: self.gc })
+            .map_err(|e| e.within(PathSegment::Field(key.to_string())))?;
+        self.map.insert(key, value);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Value> {
+        Ok(self.gc.store(self.map))
+    }
+}
+
+impl<'gc> ser::SerializeMap for MapSerializer<'gc> {
+    type Ok = Value;
+    type Error = ConversionError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        let key = key.serialize(ValueSerializer { gc: self.gc })?;
+        if !key.can_hash() {
+            return Err(ConversionError::new(format!(
+                "{} cannot be used as map key",
+                key.type_string()
+            )));
+        }
+        self.next_key = Some(key);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let key = self
+            .next_key
+            .take()
+            .expect("serialize_value called before serialize_key");
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+impl<'gc> ser::SerializeStruct for MapSerializer<'gc> {
+    type Ok = Value;
+    type Error = ConversionError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        let key = self.gc.new_string(key);
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+/// wraps serializer of variant content into single-entry map `{variant: content}`
+struct VariantSerializer<S> {
+    variant: &'static str,
+    inner: S,
+}
+
+impl<'gc> ser::SerializeTupleVariant for VariantSerializer<VectorSerializer<'gc>> {
+    type Ok = Value;
+    type Error = ConversionError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.inner
+            .push(value)
+            .map_err(|e| e.within(PathSegment::Field(self.variant.to_string())))
+    }
+
+    fn end(self) -> Result<Value> {
+        let gc = self.inner.gc;
+        let content = gc.store(self.inner.items);
+        Ok(wrap_variant(gc, self.variant, content))
+    }
+}
+
+impl<'gc> ser::SerializeStructVariant for VariantSerializer<MapSerializer<'gc>> {
+    type Ok = Value;
+    type Error = ConversionError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        let key = self.inner.gc.new_string(key);
+        self.inner
+            .insert(key, value)
+            .map_err(|e| e.within(PathSegment::Field(self.variant.to_string())))
+    }
+
+    fn end(self) -> Result<Value> {
+        let gc = self.inner.gc;
+        let content = gc.store(self.inner.map);
+        Ok(wrap_variant(gc, self.variant, content))
+    }
+}
+
+struct ValueDeserializer<'a> {
+    value: &'a Value,
+}
+
+impl<'a> ValueDeserializer<'a> {
+    fn invalid_type(&self, expected: &dyn de::Expected) -> ConversionError {
+        let unexpected = match self.value {
+            &StackObject::Int(n) => Unexpected::Signed(n),
+            &StackObject::Float(f) => Unexpected::Float(f),
+            &StackObject::Bool(b) => Unexpected::Bool(b),
+            StackObject::Nothing => Unexpected::Unit,
+            other => match other.unwrap_any_str() {
+                Some(s) => Unexpected::Str(s),
+                None => Unexpected::Other(other.type_string()),
+            },
+        };
+        de::Error::invalid_type(unexpected, expected)
+    }
+}
+
+impl<'de, 'a> de::Deserializer<'de> for ValueDeserializer<'a> {
+    type Error = ConversionError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            &StackObject::Int(n) => visitor.visit_i64(n),
+            &StackObject::Float(f) => visitor.visit_f64(f),
+            &StackObject::Bool(b) => visitor.visit_bool(b),
+            StackObject::Nothing => visitor.visit_unit(),
+            other => {
+                if let Some(s) = other.unwrap_any_str() {
+                    return visitor.visit_str(s);
+                }
+
+                match other.as_heap_object() {
+                    Some(OwnedObjectItem::Vector(items)) => {
+                        visitor.visit_seq(VectorDeserializer::new(items))
+                    }
+                    Some(OwnedObjectItem::Map(map)) => visitor.visit_map(MapDeserializer::new(
+                        map.iter().map(|(k, v)| (MapKey::Value(k), v)).collect(),
+                    )),
+                    Some(OwnedObjectItem::StructInstance(instance)) => {
+                        visitor.visit_map(MapDeserializer::from_fields(&instance.fields))
+                    }
+                    Some(OwnedObjectItem::Box(boxed)) => {
+                        ValueDeserializer { value: &boxed.0 }.deserialize_any(visitor)
+                    }
+                    //bignums never fit into i64
+                    Some(OwnedObjectItem::BigInt(n)) => {
+                        if let Some(n) = n.to_u64() {
+                            visitor.visit_u64(n)
+                        } else if let Some(n) = n.to_i128() {
+                            visitor.visit_i128(n)
+                        } else if let Some(n) = n.to_u128() {
+                            visitor.visit_u128(n)
+                        } else {
+                            Err(de::Error::custom(format!(
+                                "integer {} does not fit into 128 bits",
+                                n
+                            )))
+                        }
+                    }
+                    _ => Err(self.invalid_type(&visitor)),
+                }
+            }
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            StackObject::Nothing => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        if let Some(variant) = self.value.unwrap_any_str() {
+            return visitor.visit_enum(variant.into_deserializer());
+        }
+
+        match self.value.as_heap_object() {
+            Some(OwnedObjectItem::StructInstance(instance)) => {
+                let descriptor = instance.descriptor.unwrap_struct_descriptor().unwrap();
+                if descriptor.enum_ref.is_none() {
+                    return Err(de::Error::custom(format!(
+                        "expected enum variant, got instance of struct {}",
+                        descriptor.name
+                    )));
+                }
+                visitor.visit_enum(VariantDeserializer {
+                    variant: &descriptor.name,
+                    content: VariantContent::Fields(&instance.fields),
+                })
+            }
+
+            Some(OwnedObjectItem::Map(map)) if map.len() == 1 => {
+                let (variant, content) = map.iter().next().unwrap();
+                match variant.unwrap_any_str() {
+                    Some(variant) => visitor.visit_enum(VariantDeserializer {
+                        variant,
+                        content: VariantContent::Value(content),
+                    }),
+                    None => Err(self.invalid_type(&visitor)),
+                }
+            }
+
+            _ => Err(self.invalid_type(&visitor)),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+struct VectorDeserializer<'a> {
+    items: std::slice::Iter<'a, Value>,
+    index: usize,
+}
+
+impl<'a> VectorDeserializer<'a> {
+    fn new(items: &'a [Value]) -> Self {
+        VectorDeserializer {
+            items: items.iter(),
+            index: 0,
+        }
+    }
+}
+
+impl<'de, 'a> SeqAccess<'de> for VectorDeserializer<'a> {
+    type Error = ConversionError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+        match self.items.next() {
+            Some(value) => {
+                let index = self.index;
+                self.index += 1;
+                seed.deserialize(ValueDeserializer { value })
+                    .map(Some)
+                    .map_err(|e| e.within(PathSegment::Index(index)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len())
+    }
+}
+
+enum MapKey<'a> {
+    Value(&'a Value),
+    Name(&'a str),
+}
+
+impl<'a> MapKey<'a> {
+    fn to_segment(&self) -> PathSegment {
+        match self {
+            MapKey::Value(value) => PathSegment::Field(value.to_string()),
+            MapKey::Name(name) => PathSegment::Field(name.to_string()),
+        }
+    }
+}
+
+struct MapDeserializer<'a> {
+    entries: std::vec::IntoIter<(MapKey<'a>, &'a Value)>,
+    pending: Option<(MapKey<'a>, &'a Value)>,
+}
+
+impl<'a> MapDeserializer<'a> {
+    fn new(entries: Vec<(MapKey<'a>, &'a Value)>) -> Self {
+        MapDeserializer {
+            entries: entries.into_iter(),
+            pending: None,
+        }
+    }
+
+    fn from_fields(fields: &'a IndexMap<String, Value>) -> Self {
+        Self::new(
+            fields
+                .iter()
+                .map(|(name, value)| (MapKey::Name(name), value))
+                .collect(),
+        )
+    }
+}
+
+impl<'de, 'a> MapAccess<'de> for MapDeserializer<'a> {
+    type Error = ConversionError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        let (key, value) = match self.entries.next() {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+
+        let result = match key {
+            MapKey::Value(key) => seed.deserialize(ValueDeserializer { value: key }),
+            MapKey::Name(name) => seed.deserialize(name.into_deserializer()),
+        }
+        .map_err(|e: ConversionError| e.within(key.to_segment()))?;
+
+        self.pending = Some((key, value));
+        Ok(Some(result))
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let (key, value) = self
+            .pending
+            .take()
+            .expect("next_value_seed called before next_key_seed");
+        seed.deserialize(ValueDeserializer { value })
+            .map_err(|e| e.within(key.to_segment()))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+enum VariantContent<'a> {
+    /// fields of struct instance that represents enum variant
+    Fields(&'a IndexMap<String, Value>),
+    /// content of externally tagged variant `{variant: content}`
+    Value(&'a Value),
+}
+
+struct VariantDeserializer<'a> {
+    variant: &'a str,
+    content: VariantContent<'a>,
+}
+
+impl<'de, 'a> EnumAccess<'de> for VariantDeserializer<'a> {
+    type Error = ConversionError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self)> {
+        let variant = seed.deserialize(self.variant.into_deserializer())?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de, 'a> VariantAccess<'de> for VariantDeserializer<'a> {
+    type Error = ConversionError;
+
+    fn unit_variant(self) -> Result<()> {
+        match self.content {
+            VariantContent::Fields(fields) if fields.is_empty() => Ok(()),
+            VariantContent::Value(StackObject::Nothing) => Ok(()),
+            _ => Err(de::Error::invalid_type(
+                Unexpected::NewtypeVariant,
+                &"unit variant",
+            )),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        match self.content {
+            VariantContent::Fields(fields) if fields.len() == 1 => {
+                let (name, value) = fields.iter().next().unwrap();
+                seed.deserialize(ValueDeserializer { value })
+                    .map_err(|e| e.within(PathSegment::Field(name.clone())))
+            }
+            VariantContent::Fields(fields) => Err(de::Error::invalid_length(
+                fields.len(),
+                &"variant with single field",
+            )),
+            VariantContent::Value(value) => seed
+                .deserialize(ValueDeserializer { value })
+                .map_err(|e| e.within(PathSegment::Field(self.variant.to_string()))),
+        }
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        match self.content {
+            VariantContent::Fields(fields) => {
+                let values = fields.values().cloned().collect::<VVec>();
+                visitor.visit_seq(VectorDeserializer::new(&values))
+            }
+            VariantContent::Value(value) => {
+                de::Deserializer::deserialize_seq(ValueDeserializer { value }, visitor)
+                    .map_err(|e| e.within(PathSegment::Field(self.variant.to_string())))
+            }
+        }
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        match self.content {
+            VariantContent::Fields(fields) => {
+                visitor.visit_map(MapDeserializer::from_fields(fields))
+            }
+            VariantContent::Value(value) => {
+                de::Deserializer::deserialize_map(ValueDeserializer { value }, visitor)
+                    .map_err(|e| e.within(PathSegment::Field(self.variant.to_string())))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+    use std::collections::BTreeMap;
+
+    use super::from_value;
+    use crate::data::objects::Value;
+    use crate::execution::module::Module;
+    use crate::interpreter::Interpreter;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Server {
+        host: String,
+        port: u16,
+        tags: Vec<String>,
+        backup: Option<Box<Server>>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Shape {
+        Empty,
+        Circle(f64),
+        Rect { w: i64, h: i64 },
+    }
+
+    #[test]
+    fn rust_values_should_survive_roundtrip() {
+        let interpreter = Interpreter::new();
+
+        let server = Server {
+            host: "example.org".to_string(),
+            port: 8080,
+            tags: vec!["a".to_string(), "long tag that lives on heap".to_string()],
+            backup: Some(Box::new(Server {
+                host: "localhost".to_string(),
+                port: 1,
+                tags: vec![],
+                backup: None,
+            })),
+        };
+        let value = interpreter.to_value(&server).unwrap();
+        assert_eq!(interpreter.from_value::<Server>(&value).unwrap(), server);
+
+        let shapes = vec![Shape::Empty, Shape::Circle(0.5), Shape::Rect { w: 1, h: 2 }];
+        let value = interpreter.to_value(&shapes).unwrap();
+        assert_eq!(
+            interpreter.from_value::<Vec<Shape>>(&value).unwrap(),
+            shapes
+        );
+
+        let map: BTreeMap<String, i64> = [("x".to_string(), 1), ("y".to_string(), 2)].into();
+        let value = interpreter.to_value(&map).unwrap();
+        assert_eq!(
+            interpreter
+                .from_value::<BTreeMap<String, i64>>(&value)
+                .unwrap(),
+            map
+        );
+    }
+
+    #[test]
+    fn struct_and_enum_instances_should_be_deserialized() {
+        let interpreter = Interpreter::new();
+        let module = Module::from_dot_notation("`TEST`");
+        let source = "
+struct Server:
+    host
+    port
+    tags
+    backup
+
+enum Shape:
+    Empty
+    Circle:
+        r
+    Rect:
+        w
+        h
+
+var server = Server(\"example.org\", 8080, list(\"a\"), Nothing)
+var shapes = list(Shape.Empty(), Shape.Circle(0.5), Shape.Rect(1, 2))
+";
+        interpreter.run_source(source, &module).unwrap();
+
+        let server = interpreter.get_global(&module, "server").unwrap();
+        assert_eq!(
+            interpreter.from_value::<Server>(&server).unwrap(),
+            Server {
+                host: "example.org".to_string(),
+                port: 8080,
+                tags: vec!["a".to_string()],
+                backup: None,
+            }
+        );
+
+        let shapes = interpreter.get_global(&module, "shapes").unwrap();
+        assert_eq!(
+            interpreter.from_value::<Vec<Shape>>(&shapes).unwrap(),
+            vec![Shape::Empty, Shape::Circle(0.5), Shape::Rect { w: 1, h: 2 }]
+        );
+    }
+
+    #[test]
+    fn errors_should_contain_path() {
+        let interpreter = Interpreter::new();
+
+        let value = interpreter.to_value(&vec![(1, "a"), (2, "b")]).unwrap();
+        let error = interpreter
+            .from_value::<Vec<(i64, i64)>>(&value)
+            .unwrap_err();
+        assert_eq!(error.path(), "[0][1]");
+
+        let error = from_value::<Server>(&Value::Int(1)).unwrap_err();
+        assert_eq!(error.path(), "");
+
+        let value = interpreter.to_value(&u128::MAX).unwrap();
+        let error = interpreter.from_value::<u64>(&value).unwrap_err();
+        assert!(error
+            .message()
+            .contains("340282366920938463463374607431768211455"));
+    }
+
+    #[test]
+    fn big_integers_should_survive_roundtrip() {
+        let interpreter = Interpreter::new();
+        let module = Module::from_dot_notation("`TEST`");
+
+        let value = interpreter.to_value(&u64::MAX).unwrap();
+        assert_eq!(value.to_string(), "18446744073709551615");
+        assert_eq!(interpreter.from_value::<u64>(&value).unwrap(), u64::MAX);
+
+        let value = interpreter.to_value(&i128::MIN).unwrap();
+        assert_eq!(interpreter.from_value::<i128>(&value).unwrap(), i128::MIN);
+
+        //bignums computed by scripts are read back as well
+        interpreter.set_global(&module, "x", value);
+        let value = interpreter.run_source("x + 1", &module).unwrap();
+        assert_eq!(
+            interpreter.from_value::<i128>(&value).unwrap(),
+            i128::MIN + 1
+        );
+        let value = interpreter.to_value(&(i64::MAX as u64 + 1)).unwrap();
+        assert_eq!(
+            interpreter.from_value::<i64>(&value).unwrap_err().message(),
+            "invalid value: integer `9223372036854775808`, expected i64"
+        );
+    }
+}
@@ -1,16 +1,18 @@
 // this module defines api for working with objects from memory side
 
 use nohash_hasher::IntMap;
+use num_bigint::BigInt;
 
 use super::objects::{
-    EnumDescriptor, OwnedObject, OwnedObjectItem, StackObject, StructDescriptor, StructInstance,
-    VMap, VVec,
+    EnumDescriptor, NativeIterator, OwnedObject, OwnedObjectItem, Range, StackObject,
+    StructDescriptor, StructInstance, VMap, VVec,
 };
 use super::short_string::ShortString;
 use crate::data::marked_counter::UNMARKED_ONE;
 use crate::data::objects::{Closure, Partial, Value, ValueBox, SHORT_STRING_BUF_SIZE};
 use crate::execution::arity::Arity;
 use crate::execution::chunk::Chunk;
+use crate::execution::module::Module;
 use crate::execution::vm::CallStackValue;
 use std::pin::Pin;
 use std::ptr::NonNull;
@@ -111,68 +113,82 @@ impl OwnedObject {
         }
         self.marker.set_flag(value); //mark object itself
 
+        self.for_each_child(|child| child.mark(value)); //mark children
+    }
+
+    /// visits every value owned by object, both when marking and when counting references
+    /// between heap objects
+    fn for_each_child(&self, mut visit: impl FnMut(&StackObject)) {
         match &self.item {
             OwnedObjectItem::Map(object) => {
-                //mark children
                 for entry in object {
-                    entry.0.mark(value);
-                    entry.1.mark(value);
+                    visit(entry.0);
+                    visit(entry.1);
                 }
             }
 
             OwnedObjectItem::Vector(object) => {
-                //mark children
                 for vec_elem in object {
-                    vec_elem.mark(value);
+                    visit(vec_elem);
                 }
             }
             OwnedObjectItem::ConstantString(_) => {} //has no children
             OwnedObjectItem::Box(ptr) => {
-                ptr.0.mark(value);
+                visit(&ptr.0);
             }
             OwnedObjectItem::Closure(c) => {
-                c.underlying.mark(value);
+                visit(&c.underlying);
                 for closed_element in &c.closed_values {
-                    closed_element.mark(value);
+                    visit(closed_element);
                 }
             }
 
             OwnedObjectItem::Partial(partial) => {
-                partial.target.mark(value);
+                visit(&partial.target);
                 for stored_arg in &partial.args {
-                    stored_arg.mark(value);
+                    visit(stored_arg);
                 }
             }
 
             OwnedObjectItem::Function(chunk) => {
                 for constant in &chunk.constants {
-                    constant.mark(value);
+                    visit(constant);
                 }
             }
             OwnedObjectItem::StructDescriptor(d) => {
                 for method in d.methods.values() {
-                    method.mark(value);
+                    visit(method);
                 }
 
                 if let Some(e) = d.enum_ref.as_ref() {
-                    e.mark(value)
+                    visit(e)
                 }
             }
 
             OwnedObjectItem::EnumDescriptor(d) => {
                 for variant in d.variants.values() {
-                    variant.mark(value);
+                    visit(variant);
                 }
 
                 for method in d.methods.values() {
-                    method.mark(value);
+                    visit(method);
                 }
             }
 
             OwnedObjectItem::StructInstance(s) => {
-                s.descriptor.mark(value);
+                visit(&s.descriptor);
                 for field in s.fields.values() {
-                    field.mark(value);
+                    visit(field);
+                }
+            }
+
+            //module globals are marked as VM roots
+            OwnedObjectItem::Range(_) | OwnedObjectItem::BigInt(_) | OwnedObjectItem::Module(_) => {
+            }
+
+            OwnedObjectItem::Iterator(iterator) => {
+                for referenced in iterator.references() {
+                    visit(referenced);
                 }
             }
         }
@@ -268,6 +284,16 @@ impl OwnedObject {
                 s.fields.clear();
                 true
             }
+
+            OwnedObjectItem::Range(_) | OwnedObjectItem::BigInt(_) | OwnedObjectItem::Module(_) => {
+                false
+            }
+
+            OwnedObjectItem::Iterator(iterator) => {
+                let f = !iterator.references().is_empty();
+                *iterator = NativeIterator::Exhausted;
+                f
+            }
         }
     }
 
@@ -304,6 +330,7 @@ pub trait GCAlloc: Sized {
     } //for objects that dont need GC
 }
 
+#[allow(dead_code)]
 pub trait GCNew: GCAlloc + Default {
     fn allocate_new(gc: &mut GC) -> OwnedObject {
         Self::store(Self::default(), gc)
@@ -474,6 +501,62 @@ impl GCAlloc for StructInstance {
     }
 }
 
+impl GCAlloc for Range {
+    fn needs_gc() -> bool {
+        true
+    }
+
+    fn store(obj: Self, gc: &mut GC) -> OwnedObject {
+        OwnedObject {
+            item: OwnedObjectItem::Range(obj),
+            marker: UNMARKED_ONE,
+            owning_gc: NonNull::from(gc),
+        }
+    }
+}
+
+impl GCAlloc for BigInt {
+    fn needs_gc() -> bool {
+        true
+    }
+
+    fn store(obj: Self, gc: &mut GC) -> OwnedObject {
+        OwnedObject {
+            item: OwnedObjectItem::BigInt(obj),
+            marker: UNMARKED_ONE,
+            owning_gc: NonNull::from(gc),
+        }
+    }
+}
+
+impl GCAlloc for Module {
+    fn needs_gc() -> bool {
+        true
+    }
+
+    fn store(obj: Self, gc: &mut GC) -> OwnedObject {
+        OwnedObject {
+            item: OwnedObjectItem::Module(obj),
+            marker: UNMARKED_ONE,
+            owning_gc: NonNull::from(gc),
+        }
+    }
+}
+
+impl GCAlloc for NativeIterator {
+    fn needs_gc() -> bool {
+        true
+    }
+
+    fn store(obj: Self, gc: &mut GC) -> OwnedObject {
+        OwnedObject {
+            item: OwnedObjectItem::Iterator(obj),
+            marker: UNMARKED_ONE,
+            owning_gc: NonNull::from(gc),
+        }
+    }
+}
+
 #[cfg(feature = "verbose-gc")]
 impl Drop for OwnedObject {
     fn drop(&mut self) {
@@ -498,7 +581,10 @@ impl GC {
     ///
     /// # Arguments
     /// * `thr` - threshhold of allocations. This many allocations of objects will trigger
-    /// mark and sweeep algorithm
+    ///   mark and sweeep algorithm
+    ///
+    /// # Safety
+    /// allocated objects keep pointer to their GC, so GC should not be moved after first allocation
     pub unsafe fn new(thr: usize) -> Self {
         GC {
             objects: Default::default(),
@@ -509,6 +595,9 @@ impl GC {
         }
     }
     ///create instance of GC with default config (see GC_THR_DEFAULT)
+    ///
+    /// # Safety
+    /// see [`GC::new`]
     pub unsafe fn default_gc() -> Self {
         let thr = if cfg!(feature = "debug-gc") {
             10
@@ -552,10 +641,15 @@ impl GC {
     ///
     /// * `iter` - An iterator over roots to mark (stack, VM's constants storage and so on)
     /// * `chunks` - chunks of code. They need to be visited too as they may contain gc refs in
-    /// constants
+    ///   constants
+    ///
+    /// Besides passed roots, objects whose reference counters exceed number of references from
+    /// other heap objects are kept alive, so values held by running builtins or by host are
+    /// never collected.
     ///
-    /// thin function is unsafe because passing an iterator that does not include all possible items
-    /// will create dangling pointers
+    /// # Safety
+    /// thin function is unsafe because reference counters that are lower than actual number of
+    /// references will create dangling pointers
     pub unsafe fn mark_and_sweep<'a, I>(&mut self, iter: I, call_stack: &[CallStackValue])
     where
         I: Iterator<Item = &'a StackObject>,
@@ -567,7 +661,31 @@ impl GC {
         #[cfg(feature = "debug-gc")]
         println!("begin slow_pass");
 
+        //objects referenced from outside of heap (by builtins that are still running, by host
+        //or by VM itself) are roots: their counters exceed number of references between objects
+        let mut internal_refs: IntMap<usize, usize> = Default::default();
+        for obj in self.objects.values() {
+            obj.for_each_child(|child| {
+                if let Some(child) = child.unwrap_traceable() {
+                    *internal_refs
+                        .entry(GC::get_addressable_index(child))
+                        .or_default() += 1;
+                }
+            });
+        }
+        let external_roots = self
+            .objects
+            .iter()
+            .filter(|(addr, obj)| {
+                obj.get_gc_counter() > internal_refs.get(addr).copied().unwrap_or_default()
+            })
+            .map(|(_, obj)| NonNull::from(obj.as_ref().get_ref()))
+            .collect::<Vec<_>>();
+
         //mark
+        for mut root in external_roots {
+            root.as_mut().mark(true);
+        }
         for item in iter {
             item.mark(true);
         }
@@ -609,6 +727,7 @@ impl GC {
 
     /// drop object identified by address `addr` (probably produced by GC::get_addressable_index)
     ///
+    /// # Safety
     /// This function is unsafe because in non-debug environment existence of pointers to named
     /// object is not checked, which may lead to dropping memory that is still referenced somewhere
     pub unsafe fn drop_notify(&mut self, addr: usize) {
@@ -1,9 +1,7 @@
-use std::mem;
-
 #[derive(Copy, Clone, Debug)]
 pub struct MarkedCounter(usize);
 
-const FLAG_MASK: usize = 1 << (mem::size_of::<usize>() - 1);
+const FLAG_MASK: usize = 1 << (usize::BITS - 1);
 const MAX_COUNTER: usize = usize::MAX - FLAG_MASK;
 
 pub const UNMARKED_ONE: MarkedCounter = unsafe { MarkedCounter::new_unchecked(1, false) };
@@ -73,7 +71,6 @@ impl MarkedCounter {
 #[cfg(test)]
 mod tests {
     use crate::data::marked_counter::MarkedCounter;
-    use std::mem;
 
     #[test]
     fn should_keep_value() {
@@ -84,7 +81,7 @@ mod tests {
 
     #[test]
     fn should_work_for_big_values() {
-        let big_value = usize::MAX - (1 << (mem::size_of::<usize>() - 1));
+        let big_value = usize::MAX >> 1;
         let counter = MarkedCounter::new(big_value, false);
         assert!(!counter.flag());
         assert_eq!(counter.counter(), big_value);
@@ -1,3 +1,4 @@
+pub mod conversion;
 pub mod gc;
 pub mod marked_counter;
 pub mod objects;
@@ -1,8 +1,10 @@
 use crate::data::marked_counter::{MarkedCounter, UNMARKED_ONE};
 use crate::execution::arity::Arity;
+use crate::execution::chunk::Chunk;
+use crate::execution::module::{is_private_name, Module};
 use crate::execution::vm::VM;
-use crate::Chunk;
 use indexmap::IndexMap;
+use num_bigint::BigInt;
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::fmt::{Debug, Display, Formatter};
@@ -16,14 +18,19 @@ pub type Value = StackObject;
 
 pub const SHORT_STRING_BUF_SIZE: usize = 8;
 
+#[derive(Default)]
 pub enum StackObject {
     Int(i64),
     Float(f64),
     Bool(bool),
+    #[default]
     Nothing,
     Blank,
     Builtin(usize),
-    BuiltinMethod { class_idx: u32, method_idx: u32 },
+    BuiltinMethod {
+        class_idx: u32,
+        method_idx: u32,
+    },
     ShortString(ShortString<SHORT_STRING_BUF_SIZE>),
     HeapObject(PrivatePtr<OwnedObject>),
 }
@@ -46,12 +53,6 @@ impl From<bool> for StackObject {
     }
 }
 
-impl Default for StackObject {
-    fn default() -> Self {
-        Self::Nothing
-    }
-}
-
 pub struct OwnedObject {
     pub item: OwnedObjectItem,
     pub marker: MarkedCounter,
@@ -186,7 +187,7 @@ impl StructDescriptor {
                 .fields
                 .iter()
                 .cloned()
-                .zip(args.into_iter())
+                .zip(args)
                 .collect::<IndexMap<String, Value>>(),
         })
     }
@@ -301,6 +302,7 @@ impl StructInstance {
             .or_else(|| self.get_field(entity_name))
     }
 
+    #[allow(clippy::result_unit_err)]
     pub fn set_field(&mut self, field_name: &str, value: Value) -> Result<(), ()> {
         if self.fields.contains_key(field_name) {
             self.fields.insert(field_name.to_string(), value);
@@ -311,6 +313,122 @@ impl StructInstance {
     }
 }
 
+/// integer sequence `start, start + step, ...` which stops before reaching `stop`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Range {
+    pub start: i64,
+    pub stop: i64,
+    pub step: i64,
+}
+
+impl Range {
+    /// returns `None` if step is zero
+    pub fn new(start: i64, stop: i64, step: i64) -> Option<Range> {
+        (step != 0).then_some(Range { start, stop, step })
+    }
+
+    pub fn len(&self) -> usize {
+        let (start, stop, step) = (self.start as i128, self.stop as i128, self.step as i128);
+        let span = if step > 0 { stop - start } else { start - stop };
+        if span <= 0 {
+            0
+        } else {
+            ((span - 1) / step.abs() + 1) as usize
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, idx: usize) -> Option<i64> {
+        (idx < self.len()).then(|| self.start + idx as i64 * self.step)
+    }
+
+    pub fn contains(&self, n: i64) -> bool {
+        let offset = n as i128 - self.start as i128;
+        offset % self.step as i128 == 0
+            && offset / (self.step as i128) >= 0
+            && ((offset / self.step as i128) as usize) < self.len()
+    }
+}
+
+/// state of lazy iteration, advanced by `VM::iterator_next`. Iterators never materialize
+/// their source, except for maps which are iterated over snapshot of their keys
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NativeIterator {
+    Range {
+        range: Range,
+        position: usize,
+    },
+    Vector {
+        source: Value,
+        position: usize,
+    },
+    /// `position` is byte offset of next character
+    Chars {
+        source: Value,
+        position: usize,
+    },
+    Keys {
+        keys: VVec,
+        position: usize,
+    },
+    /// user struct implementing `next` method, which returns `Nothing` when exhausted
+    Protocol(Value),
+    Map {
+        inner: Value,
+        function: Value,
+    },
+    Filter {
+        inner: Value,
+        predicate: Value,
+    },
+    Take {
+        inner: Value,
+        remaining: usize,
+    },
+    Enumerate {
+        inner: Value,
+        position: usize,
+    },
+    /// suspended frame of generator function: its stack slice (starting with called function
+    /// itself, i.e. at locals offset of the frame) and instruction to resume from
+    Generator {
+        function: Value,
+        frame: VVec,
+        ip: usize,
+    },
+    /// generator whose frame is currently on VM stack
+    Running,
+    /// iterators release their sources as soon as they are exhausted
+    Exhausted,
+}
+
+impl NativeIterator {
+    /// values that should be kept alive while iterator is reachable
+    pub fn references(&self) -> Vec<&Value> {
+        match self {
+            NativeIterator::Range { .. } | NativeIterator::Running | NativeIterator::Exhausted => {
+                vec![]
+            }
+            NativeIterator::Vector { source, .. } | NativeIterator::Chars { source, .. } => {
+                vec![source]
+            }
+            NativeIterator::Keys { keys, .. } => keys.iter().collect(),
+            NativeIterator::Protocol(object) => vec![object],
+            NativeIterator::Map { inner, function } => vec![inner, function],
+            NativeIterator::Filter { inner, predicate } => vec![inner, predicate],
+            NativeIterator::Take { inner, .. } | NativeIterator::Enumerate { inner, .. } => {
+                vec![inner]
+            }
+            NativeIterator::Generator {
+                function, frame, ..
+            } => std::iter::once(function).chain(frame.iter()).collect(),
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum OwnedObjectItem {
     ConstantString(String),
@@ -323,6 +441,13 @@ pub enum OwnedObjectItem {
     StructDescriptor(StructDescriptor),
     EnumDescriptor(EnumDescriptor),
     StructInstance(StructInstance),
+    Range(Range),
+    Iterator(NativeIterator),
+    /// integer that does not fit into `Int`. Integers are always normalized, i.e. values in
+    /// range of i64 are never stored as bignums
+    BigInt(BigInt),
+    /// module bound by `import a.b`, its globals are accessed as fields
+    Module(Module),
 }
 
 pub type VVec = Vec<StackObject>;
@@ -355,7 +480,7 @@ impl<T> PrivatePtr<T> {
 
 impl<T> Clone for PrivatePtr<T> {
     fn clone(&self) -> Self {
-        PrivatePtr { ptr: self.ptr }
+        *self
     }
 }
 
@@ -567,6 +692,32 @@ impl StackObject {
         }
     }
 
+    pub fn unwrap_range(&self) -> Option<&mut Range> {
+        match self.as_heap_object() {
+            Some(OwnedObjectItem::Range(r)) => Some(r),
+            _ => None,
+        }
+    }
+
+    pub fn unwrap_iterator(&self) -> Option<&mut NativeIterator> {
+        match self.as_heap_object() {
+            Some(OwnedObjectItem::Iterator(i)) => Some(i),
+            _ => None,
+        }
+    }
+
+    pub fn unwrap_bigint(&self) -> Option<&BigInt> {
+        match self.as_heap_object() {
+            Some(OwnedObjectItem::BigInt(n)) => Some(n),
+            _ => None,
+        }
+    }
+
+    /// true for both small integers and bignums
+    pub fn is_integer(&self) -> bool {
+        matches!(self, StackObject::Int(..)) || self.unwrap_bigint().is_some()
+    }
+
     pub fn unwrap_any_str(&self) -> Option<&str> {
         match self {
             StackObject::ShortString(s) => Some(s.as_str()),
@@ -604,6 +755,10 @@ impl StackObject {
                 OwnedObjectItem::Partial(p) => Some(p.get_arity()),
                 OwnedObjectItem::StructDescriptor(s) => Some(Arity::Exact(s.fields.len())),
                 OwnedObjectItem::StructInstance(_) => None,
+                OwnedObjectItem::Range(_)
+                | OwnedObjectItem::Iterator(_)
+                | OwnedObjectItem::BigInt(_)
+                | OwnedObjectItem::Module(_) => None,
             },
         }
     }
@@ -614,19 +769,36 @@ impl StackObject {
                 OwnedObjectItem::Box(_) => None,
                 OwnedObjectItem::StructDescriptor(_) => None,
                 OwnedObjectItem::EnumDescriptor(e) => e.lookup(field_name),
-                OwnedObjectItem::StructInstance(i) => i.lookup(self, field_name, context),
+                OwnedObjectItem::Module(_) if is_private_name(field_name) => None,
+                OwnedObjectItem::Module(module) => context.get_global(module, field_name),
+                OwnedObjectItem::StructInstance(i) => {
+                    i.lookup(self, field_name, context).or_else(|| {
+                        let struct_name = i
+                            .descriptor
+                            .unwrap_struct_descriptor()
+                            .unwrap()
+                            .name
+                            .clone();
+                        Self::bind_builtin_method(self.clone(), &struct_name, field_name, context)
+                    })
+                }
 
-                _ => Self::bind_builtin_method(self.clone(), field_name, context),
+                _ => {
+                    Self::bind_builtin_method(self.clone(), self.type_string(), field_name, context)
+                }
             },
 
-            _ => Self::bind_builtin_method(self.clone(), field_name, context),
+            _ => Self::bind_builtin_method(self.clone(), self.type_string(), field_name, context),
         }
     }
 
-    fn bind_builtin_method(object: Value, method_name: &str, context: &mut VM) -> Option<Value> {
-        let method = context
-            .builtins
-            .get_method(object.type_string(), method_name)?;
+    fn bind_builtin_method(
+        object: Value,
+        class_name: &str,
+        method_name: &str,
+        context: &mut VM,
+    ) -> Option<Value> {
+        let method = context.builtins.get_method(class_name, method_name)?;
 
         let arity = match method {
             StackObject::BuiltinMethod {
@@ -642,6 +814,7 @@ impl StackObject {
         Some(context.gc.store(partial))
     }
 
+    #[allow(clippy::result_unit_err)]
     pub fn set_field(&self, field_name: &str, value: Value, _context: &mut VM) -> Result<(), ()> {
         match self {
             h @ StackObject::HeapObject(..) => match h.as_heap_object().unwrap() {
@@ -686,7 +859,11 @@ impl StackObject {
     fn cmp_ints(obj1: &StackObject, obj2: &StackObject) -> Option<Ordering> {
         match (obj1.unwrap_int(), obj2.unwrap_int()) {
             (Some(n1), Some(n2)) => Some(n1.cmp(&n2)),
-            _ => None,
+            _ => {
+                let n1 = crate::data::value_ops::to_bigint(obj1)?;
+                let n2 = crate::data::value_ops::to_bigint(obj2)?;
+                Some(n1.cmp(&n2))
+            }
         }
     }
 
@@ -752,7 +929,10 @@ impl Hash for StackObject {
 #[allow(dead_code)]
 impl OwnedObject {
     pub fn can_hash(&self) -> bool {
-        matches!(&self.item, OwnedObjectItem::ConstantString(..))
+        matches!(
+            &self.item,
+            OwnedObjectItem::ConstantString(..) | OwnedObjectItem::BigInt(..)
+        )
     }
 
     fn unwrap_map(&mut self) -> Option<&mut VMap> {
@@ -781,6 +961,10 @@ impl OwnedObject {
             OwnedObjectItem::StructDescriptor(..) => "StructDescriptor",
             OwnedObjectItem::StructInstance(..) => "Struct",
             OwnedObjectItem::EnumDescriptor(..) => "Enum",
+            OwnedObjectItem::Range(..) => "Range",
+            OwnedObjectItem::Iterator(..) => "Iterator",
+            OwnedObjectItem::BigInt(..) => "Int",
+            OwnedObjectItem::Module(..) => "Module",
         }
     }
 }
@@ -789,6 +973,7 @@ impl Hash for OwnedObject {
     fn hash<H: Hasher>(&self, _state: &mut H) {
         match &self.item {
             OwnedObjectItem::ConstantString(s) => s.hash(_state),
+            OwnedObjectItem::BigInt(n) => n.hash(_state),
 
             _ => panic!("unhashable type {:?}", self),
         }
@@ -847,6 +1032,12 @@ impl Debug for OwnedObject {
                     instance.descriptor, instance.fields
                 )
             }
+
+            OwnedObjectItem::Range(range) => format!("{:?}", range),
+            OwnedObjectItem::BigInt(n) => format!("BigInt {}", n),
+            OwnedObjectItem::Module(module) => format!("Module {}", module),
+
+            OwnedObjectItem::Iterator(iterator) => format!("{:?} at {:p}", iterator, self),
         };
 
         write!(f, "object [{}], RC={}", content, self.marker.counter())
@@ -905,6 +1096,22 @@ impl Display for OwnedObject {
                         .join(", ")
                 )
             }
+            OwnedObjectItem::Range(Range { start, stop, step }) => {
+                write!(f, "range({}, {}, {})", start, stop, step)
+            }
+            OwnedObjectItem::Iterator(NativeIterator::Generator { function, .. }) => write!(
+                f,
+                "generator {}",
+                function
+                    .unwrap_function()
+                    .unwrap()
+                    .name
+                    .get_string()
+                    .unwrap()
+            ),
+            OwnedObjectItem::Iterator(..) => write!(f, "iterator"),
+            OwnedObjectItem::BigInt(n) => write!(f, "{}", n),
+            OwnedObjectItem::Module(module) => write!(f, "module {}", module),
         }
     }
 }
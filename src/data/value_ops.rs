@@ -1,4 +1,8 @@
+use crate::data::gc::GC;
 use crate::data::objects::Value;
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::ToPrimitive;
 
 #[derive(Copy, Clone, Debug)]
 pub enum NumberCastResult {
@@ -15,23 +19,178 @@ impl NumberCastResult {
     }
 }
 
+/// bignums are cast to floats, so integer operations should be handled before falling back
+/// to this cast (see `integer_binary`)
 pub fn numeric_cast(value: &Value) -> Option<NumberCastResult> {
     value
         .unwrap_int()
         .map(NumberCastResult::Int)
         .or_else(|| value.unwrap_float().map(NumberCastResult::Float))
+        .or_else(|| {
+            value
+                .unwrap_bigint()
+                .map(|n| NumberCastResult::Float(n.to_f64().unwrap_or(f64::NAN)))
+        })
 }
 
 pub fn equality_operator(left: &Value, right: &Value) -> bool {
-    left.eq(right) || {
-        let left = numeric_cast(left);
-        let right = numeric_cast(right);
-        left.and(right)
-            .map(|_| left.unwrap().downgrade() == right.unwrap().downgrade())
-            .unwrap_or(false)
+    left.eq(right)
+        //integers are normalized, so small int is never equal to bignum
+        || !(left.is_integer() && right.is_integer()) && {
+            let left = numeric_cast(left);
+            let right = numeric_cast(right);
+            left.and(right)
+                .map(|_| left.unwrap().downgrade() == right.unwrap().downgrade())
+                .unwrap_or(false)
+        }
+}
+
+pub fn to_bigint(value: &Value) -> Option<BigInt> {
+    match value {
+        &Value::Int(n) => Some(BigInt::from(n)),
+        other => other.unwrap_bigint().cloned(),
+    }
+}
+
+/// largest integer that `<<` and `**` may produce, in bits. Results are checked before they are
+/// computed both at runtime and when folding constants, as computing bigger ones takes
+/// unbounded time and memory
+pub const MAX_INTEGER_BITS: u64 = 1 << 20;
+
+/// number of significant bits of integer's absolute value, `None` for other values
+pub fn integer_bits(value: &Value) -> Option<u64> {
+    match value {
+        &Value::Int(n) => Some(u64::from(u64::BITS - n.unsigned_abs().leading_zeros())),
+        other => Some(other.unwrap_bigint()?.bits()),
     }
 }
 
+/// checks that shifting integer with `bits` significant bits left by `shift` stays within
+/// [`MAX_INTEGER_BITS`]
+pub fn shift_fits(bits: u64, shift: u64) -> bool {
+    bits == 0 || bits.saturating_add(shift) <= MAX_INTEGER_BITS
+}
+
+/// stores integer as `Int` if it fits into 64 bits, otherwise allocates bignum
+pub fn normalize_bigint(n: BigInt, gc: &mut GC) -> Value {
+    match n.to_i64() {
+        Some(small) => Value::Int(small),
+        None => gc.store(n),
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum IntegerOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    FloorDiv,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
+}
+
+/// integer arithmetic that promotes result to bignum on overflow. Returns `None` if any of
+/// operands is not an integer. Caller is responsible for checking division by zero and for
+/// checking that shift count is non-negative and that left shift [fits](shift_fits)
+pub fn integer_binary(left: &Value, right: &Value, op: IntegerOp, gc: &mut GC) -> Option<Value> {
+    if let (&Value::Int(a), &Value::Int(b)) = (left, right) {
+        let result = match op {
+            IntegerOp::Add => a.checked_add(b),
+            IntegerOp::Sub => a.checked_sub(b),
+            IntegerOp::Mul => a.checked_mul(b),
+            IntegerOp::Div => a.checked_div(b),
+            IntegerOp::Rem => a.checked_rem(b),
+            //i64::MIN // -1 overflows
+            IntegerOp::FloorDiv => a.checked_div(b).map(|_| Integer::div_floor(&a, &b)),
+            IntegerOp::BitAnd => Some(a & b),
+            IntegerOp::BitOr => Some(a | b),
+            IntegerOp::BitXor => Some(a ^ b),
+            //shift overflows if any significant bit is lost
+            IntegerOp::Shl if b < 64 => Some(a << b).filter(|shifted| shifted >> b == a),
+            IntegerOp::Shl => (a == 0).then_some(0),
+            IntegerOp::Shr => Some(a >> b.min(63)),
+        };
+        if let Some(n) = result {
+            return Some(Value::Int(n));
+        }
+    }
+
+    let (a, b) = (to_bigint(left)?, to_bigint(right)?);
+    let result = match op {
+        IntegerOp::Add => a + b,
+        IntegerOp::Sub => a - b,
+        IntegerOp::Mul => a * b,
+        IntegerOp::Div => a / b,
+        IntegerOp::Rem => a % b,
+        IntegerOp::FloorDiv => Integer::div_floor(&a, &b),
+        IntegerOp::BitAnd => a & b,
+        IntegerOp::BitOr => a | b,
+        IntegerOp::BitXor => a ^ b,
+        IntegerOp::Shl => a << b.to_usize()?,
+        //any count beyond bit length leaves only sign
+        IntegerOp::Shr => a >> b.to_usize().unwrap_or(usize::MAX),
+    };
+    Some(normalize_bigint(result, gc))
+}
+
+/// `base ** exponent`, `None` if result would exceed [`MAX_INTEGER_BITS`]
+pub fn checked_pow(base: &BigInt, exponent: u64) -> Option<BigInt> {
+    let bits = base.bits();
+    //powers of 0, 1 and -1 only depend on parity of exponent
+    let exponent = match bits {
+        0 | 1 if exponent > 2 => 2 - exponent % 2,
+        0 | 1 => exponent,
+        _ if bits.saturating_mul(exponent) > MAX_INTEGER_BITS => return None,
+        _ => exponent,
+    };
+    Some(base.pow(u32::try_from(exponent).ok()?))
+}
+
+/// integer power with promotion to bignum, `None` if base is not an integer or result would
+/// exceed [`MAX_INTEGER_BITS`]
+pub fn integer_pow(base: &Value, exponent: u64, gc: &mut GC) -> Option<Value> {
+    if let (&Value::Int(a), Ok(small_exponent)) = (base, u32::try_from(exponent)) {
+        if let Some(n) = a.checked_pow(small_exponent) {
+            return Some(Value::Int(n));
+        }
+    }
+    Some(normalize_bigint(
+        checked_pow(&to_bigint(base)?, exponent)?,
+        gc,
+    ))
+}
+
+/// bitwise negation, `~x == -x - 1`
+pub fn integer_not(value: &Value, gc: &mut GC) -> Option<Value> {
+    match value {
+        &Value::Int(n) => Some(Value::Int(!n)),
+        other => Some(normalize_bigint(!other.unwrap_bigint()?.clone(), gc)),
+    }
+}
+
+/// negates number, promoting `-i64::MIN` to bignum
+pub fn negate(value: &Value, gc: &mut GC) -> Option<Value> {
+    match *value {
+        Value::Int(n) => Some(
+            n.checked_neg()
+                .map(Value::Int)
+                .unwrap_or_else(|| normalize_bigint(-BigInt::from(n), gc)),
+        ),
+        Value::Float(f) => Some(Value::Float(-f)),
+        ref other => Some(normalize_bigint(-other.unwrap_bigint()?.clone(), gc)),
+    }
+}
+
+pub fn is_zero(value: &Value) -> bool {
+    //bignums are never zero
+    matches!(*value, Value::Int(0)) || value.unwrap_float().map(|f| f == 0f64) == Some(true)
+}
+
 macro_rules! cast_binary {
     ($left:expr, $op:tt, $right:expr ) => {
         match ($left, $right) {
@@ -171,6 +330,78 @@ mod tests {
         assert!(!equality_operator(&12i64.into(), &10f64.into()));
     }
 
+    #[rstest]
+    fn integer_ops_should_promote_on_overflow(mut gc: GC) {
+        let max = Value::Int(i64::MAX);
+        let promoted = integer_binary(&max, &Value::Int(1), IntegerOp::Add, &mut gc).unwrap();
+        assert_eq!(
+            promoted.unwrap_bigint(),
+            Some(&(BigInt::from(i64::MAX) + 1))
+        );
+
+        //results that fit into 64 bits are normalized back
+        let back = integer_binary(&promoted, &Value::Int(1), IntegerOp::Sub, &mut gc).unwrap();
+        assert_eq!(back, max);
+
+        let min_div = integer_binary(
+            &Value::Int(i64::MIN),
+            &Value::Int(-1),
+            IntegerOp::Div,
+            &mut gc,
+        );
+        assert!(min_div.unwrap().unwrap_bigint().is_some());
+        assert_eq!(
+            integer_pow(&Value::Int(2), 64, &mut gc)
+                .unwrap()
+                .unwrap_bigint(),
+            Some(&(BigInt::from(1) << 64))
+        );
+        assert!(integer_binary(&max, &1.5.into(), IntegerOp::Add, &mut gc).is_none());
+        drop(promoted);
+    }
+
+    #[rstest]
+    fn bitwise_ops_should_work_for_small_and_big_ints(mut gc: GC) {
+        let mut op = |left: i64, right: i64, op| {
+            integer_binary(&Value::Int(left), &Value::Int(right), op, &mut gc).unwrap()
+        };
+        assert_eq!(op(-7, 2, IntegerOp::FloorDiv), Value::Int(-4));
+        assert_eq!(op(0b1100, 0b1010, IntegerOp::BitAnd), Value::Int(0b1000));
+        assert_eq!(op(0b1100, 0b1010, IntegerOp::BitOr), Value::Int(0b1110));
+        assert_eq!(op(0b1100, 0b1010, IntegerOp::BitXor), Value::Int(0b0110));
+        assert_eq!(op(-1, 100, IntegerOp::Shr), Value::Int(-1));
+        assert_eq!(op(0, 1000, IntegerOp::Shl), Value::Int(0));
+
+        let shifted = op(1, 70, IntegerOp::Shl);
+        assert_eq!(shifted.unwrap_bigint(), Some(&(BigInt::from(1) << 70)));
+        let back = integer_binary(&shifted, &Value::Int(70), IntegerOp::Shr, &mut gc).unwrap();
+        assert_eq!(back, Value::Int(1));
+        assert_eq!(integer_not(&Value::Int(5), &mut gc), Some(Value::Int(-6)));
+        drop(shifted);
+    }
+
+    #[rstest]
+    fn bignums_should_compare_and_hash_by_value(mut gc: GC) {
+        let big1 = normalize_bigint(BigInt::from(i64::MAX) + 1, &mut gc);
+        let big2 = normalize_bigint(BigInt::from(i64::MAX) + 1, &mut gc);
+        let max = Value::Int(i64::MAX);
+
+        assert!(equality_operator(&big1, &big2));
+        //both are equal to 2^63 when cast to floats
+        assert!(!equality_operator(&big1, &max));
+        assert_eq!(
+            comparison_operator!(&max, &big1, Ordering::Less),
+            Some(true)
+        );
+
+        let mut map = crate::data::objects::VMap::default();
+        map.insert(big1.clone(), Value::Int(1));
+        assert_eq!(map.get(&big2), Some(&Value::Int(1)));
+        drop(map);
+        drop(big1);
+        drop(big2);
+    }
+
     #[test]
     fn cmp_should_work_for_mixed_numbers() {
         assert_eq!(
@@ -1,25 +1,83 @@
-use std::{collections::HashMap, fmt::Display};
+use std::{cmp::Ordering, collections::HashMap, fmt::Display};
 
 ///
 /// contract: all builtin functions may change vm state, but they should never touch VM's buitin_map as it may be aliased
-use crate::data::objects::{StackObject, VVec, Value};
+use crate::data::objects::{NativeIterator, OwnedObjectItem, Range, StackObject, VVec, Value};
+use crate::data::value_ops::{
+    equality_operator, integer_binary, normalize_bigint, numeric_cast, to_bigint, IntegerOp,
+};
 use indexmap::IndexMap;
+use num_bigint::BigInt;
+use num_traits::{Signed, ToPrimitive};
 
-use super::{arity::Arity, vm::VM};
+use super::{
+    arity::Arity,
+    module::Module,
+    vm::{InterpretError, ModuleError, VM},
+};
 
-type BuiltinFuction = fn(Vec<Value>, &mut VM) -> Result;
+/// longest string `repeat` may produce, in bytes. Bigger allocations would abort the process
+/// instead of failing with an error
+const MAX_REPEATED_LEN: usize = 1 << 30;
 
-type BuiltinMethod = fn(Value, Vec<Value>, &mut VM) -> Result;
+/// native function. Closures may capture state, but as builtins are shared between all callers
+/// any mutable state should be wrapped in something like `Cell` or `RefCell`
+pub type BuiltinFunction = dyn Fn(Vec<Value>, &mut VM) -> Result;
+
+/// native method, receives bound object as first argument
+pub type BuiltinMethod = dyn Fn(Value, Vec<Value>, &mut VM) -> Result;
 
 #[derive(Default)]
 pub struct BuiltinMap {
-    functions: IndexMap<String, (Arity, BuiltinFuction)>,
-    methods: IndexMap<String, IndexMap<String, (Arity, BuiltinMethod)>>,
+    functions: IndexMap<String, (Arity, Box<BuiltinFunction>)>,
+    methods: IndexMap<String, IndexMap<String, (Arity, Box<BuiltinMethod>)>>,
     builtin_values: HashMap<String, Value>,
+    modules: HashMap<Module, HashMap<String, Value>>,
+}
+
+/// named group of native functions and values that can be imported from scripts like any other
+/// module, e.g. functions of module `host.math` are available via `import host.math.sqrt`
+pub struct NativeModule {
+    name: Module,
+    functions: IndexMap<String, (Arity, Box<BuiltinFunction>)>,
+    values: HashMap<String, Value>,
+}
+
+impl NativeModule {
+    pub fn new(module_name: &str) -> Self {
+        NativeModule {
+            name: Module::from_dot_notation(module_name),
+            functions: Default::default(),
+            values: Default::default(),
+        }
+    }
+
+    pub fn add_function(
+        &mut self,
+        name: &str,
+        arity: Arity,
+        f: impl Fn(Vec<Value>, &mut VM) -> Result + 'static,
+    ) {
+        self.functions.insert(name.to_owned(), (arity, Box::new(f)));
+    }
+
+    /// adds constant to module. Heap-allocated values are rejected as builtins live outside of GC
+    pub fn add_value(&mut self, name: &str, value: Value) -> std::result::Result<(), String> {
+        check_immediate(name, &value)?;
+        self.values.insert(name.to_owned(), value);
+        Ok(())
+    }
 }
 
 pub enum BuiltinError {
-    ArityMismatch { provided: usize, expected: Arity },
+    ArityMismatch {
+        provided: usize,
+        expected: Arity,
+    },
+    /// error raised by user code called from inside builtin
+    Interpret(InterpretError),
+    /// script called `exit(code)`
+    Exit(i32),
     Other(String),
 }
 
@@ -31,6 +89,8 @@ impl Display for BuiltinError {
             match self {
                 BuiltinError::ArityMismatch { provided, expected } =>
                     format!("expected {} args but got {}", expected, provided),
+                BuiltinError::Interpret(e) => format!("{:?}", e.kind),
+                BuiltinError::Exit(code) => format!("exit({})", code),
                 BuiltinError::Other(e) => e.clone(),
             }
         )
@@ -43,28 +103,70 @@ impl From<String> for BuiltinError {
     }
 }
 
-type Result = std::result::Result<Value, BuiltinError>;
+impl From<InterpretError> for BuiltinError {
+    fn from(e: InterpretError) -> Self {
+        BuiltinError::Interpret(e)
+    }
+}
+
+pub type BuiltinResult = std::result::Result<Value, BuiltinError>;
+
+type Result = BuiltinResult;
 
 impl BuiltinMap {
-    pub(self) fn new() -> Self {
+    pub fn new() -> Self {
         Default::default()
     }
 
-    pub(self) fn add_builtin(&mut self, name: &'static str, arity: Arity, f: BuiltinFuction) {
-        self.functions.insert(name.to_owned(), (arity, f));
+    pub fn add_builtin(
+        &mut self,
+        name: &str,
+        arity: Arity,
+        f: impl Fn(Vec<Value>, &mut VM) -> Result + 'static,
+    ) {
+        self.functions.insert(name.to_owned(), (arity, Box::new(f)));
     }
 
-    pub(self) fn add_method(
+    /// adds method to objects of type `classname` (as reported by `type_string`, or name of
+    /// struct for struct instances). Note that `arity` includes bound object
+    pub fn add_method(
         &mut self,
         classname: &str,
         method_name: &str,
         arity: Arity,
-        f: BuiltinMethod,
+        f: impl Fn(Value, Vec<Value>, &mut VM) -> Result + 'static,
     ) {
         self.methods
             .entry(classname.to_string())
             .or_default()
-            .insert(method_name.to_string(), (arity, f));
+            .insert(method_name.to_string(), (arity, Box::new(f)));
+    }
+
+    /// adds global value visible from all modules. Heap-allocated values are rejected
+    pub fn add_value(&mut self, name: &str, value: Value) -> std::result::Result<(), String> {
+        check_immediate(name, &value)?;
+        self.builtin_values.insert(name.to_owned(), value);
+        Ok(())
+    }
+
+    /// registers native module. Its functions are stored under fully qualified names
+    /// (like `host.math.sqrt`) so they are never visible as globals
+    pub fn add_module(&mut self, module: NativeModule) {
+        let module_name = module.name.to_string();
+        let mut items = module.values;
+
+        for (name, (arity, f)) in module.functions {
+            let (idx, _) = self
+                .functions
+                .insert_full(format!("{}.{}", module_name, name), (arity, f));
+            items.insert(name, Value::Builtin(idx));
+        }
+
+        self.modules.entry(module.name).or_default().extend(items);
+    }
+
+    pub fn get_module(&self, module: &Module) -> Option<&HashMap<String, Value>> {
+        self.modules.get(module)
     }
 
     pub fn apply_builtin(&self, idx: usize, args: VVec, vm: &mut VM) -> Result {
@@ -93,7 +195,7 @@ impl BuiltinMap {
                             class_name.as_str(),
                             method_name.as_str(),
                             method.0,
-                            &method.1,
+                            method.1.as_ref(),
                         )
                     })
             })
@@ -124,6 +226,7 @@ impl BuiltinMap {
     pub fn get_builtin(&self, name: &str) -> Option<Value> {
         self.functions
             .get_full(name)
+            .filter(|(_, name, _)| !name.contains('.'))
             .map(|(idx, _, _)| Value::Builtin(idx))
             .or_else(|| self.builtin_values.get(name).cloned())
     }
@@ -160,6 +263,18 @@ impl BuiltinMap {
     }
 }
 
+/// GC does not trace builtins, so heap objects stored there would be freed under them
+fn check_immediate(name: &str, value: &Value) -> std::result::Result<(), String> {
+    match value.as_heap_object() {
+        Some(_) => Err(format!(
+            "builtin value {} should not be heap-allocated, got {}",
+            name,
+            value.type_string()
+        )),
+        None => Ok(()),
+    }
+}
+
 fn check_arity(arity: Arity, provided: usize) -> std::result::Result<(), BuiltinError> {
     if !arity.accepts(provided) {
         Err(BuiltinError::ArityMismatch {
@@ -178,7 +293,7 @@ pub fn builtin_factory() -> BuiltinMap {
 
     macro_rules! value {
         ($name:expr, $_value:expr) => {
-            map.builtin_values.insert($name.to_string(), $_value);
+            map.add_value($name, $_value).unwrap()
         };
     }
 
@@ -200,13 +315,31 @@ pub fn builtin_factory() -> BuiltinMap {
 
     value!("Nothing", Default::default());
 
-    builtin!("sum", AtLeast(0), |args, _vm| {
+    builtin!("sum", AtLeast(0), |args, vm| {
+        //single non-numeric argument is consumed as iterable, e.g. `sum(range(10))`
+        if let [iterable] = args[0].unwrap_vector().unwrap().as_slice() {
+            if !iterable.is_integer() {
+                let iterator = vm.make_iterator(iterable.clone())?;
+                let mut total = Value::Int(0);
+                while let Some(item) = vm.iterator_next(&iterator)? {
+                    total =
+                        integer_binary(&total, &item, IntegerOp::Add, vm.gc).ok_or_else(|| {
+                            BuiltinError::Other(format!(
+                                "expected all items of type int, got {}",
+                                item.type_string()
+                            ))
+                        })?;
+                }
+                return Ok(total);
+            }
+        }
+
         if let Some((idx, obj)) = args[0]
             .unwrap_vector()
             .unwrap()
             .iter()
             .enumerate()
-            .find(|(_idx, v)| v.unwrap_int().is_none())
+            .find(|(_idx, v)| !v.is_integer())
         {
             return Err(format!(
                 "expected all args of type int, got {} arg of {}",
@@ -216,33 +349,93 @@ pub fn builtin_factory() -> BuiltinMap {
             .into());
         }
 
-        Ok(Value::Int(
-            args[0]
-                .unwrap_vector()
-                .unwrap()
-                .iter()
-                .map(|v| v.unwrap_int().unwrap())
-                .sum(),
-        ))
+        let mut total = Value::Int(0);
+        for item in args[0].unwrap_vector().unwrap().iter() {
+            total = integer_binary(&total, item, IntegerOp::Add, vm.gc).unwrap();
+        }
+        Ok(total)
     });
 
-    builtin!("int", Exact(1), |args, _vm| {
+    //floats are truncated towards zero
+    builtin!("int", Exact(1), |args, vm| {
+        match args[0] {
+            Value::Float(f) => return super::math::float_to_int("int", f.trunc(), vm),
+            ref n if n.is_integer() => return Ok(n.clone()),
+            _ => {}
+        }
         if args[0].unwrap_any_str().is_none() {
-            return Err("expected string-like in int".to_string().into());
+            return Err("expected string-like or number in int".to_string().into());
+        }
+        let n = args[0]
+            .unwrap_any_str()
+            .unwrap()
+            .parse::<BigInt>()
+            .map_err(|_e| BuiltinError::Other(format!("failed to parse {}", args[0])))?;
+        Ok(normalize_bigint(n, vm.gc))
+    });
+
+    builtin!("float", Exact(1), |args, _vm| {
+        match args[0] {
+            Value::Int(n) => Ok(Value::Float(n as f64)),
+            Value::Float(f) => Ok(Value::Float(f)),
+            ref big if big.is_integer() => Ok(Value::Float(
+                big.unwrap_bigint().unwrap().to_f64().unwrap_or(f64::NAN),
+            )),
+            _ => {
+                let s = args[0].unwrap_any_str().ok_or_else(|| {
+                    BuiltinError::Other(format!(
+                        "expected string-like or number in float, got {}",
+                        args[0].type_string()
+                    ))
+                })?;
+                s.trim()
+                    .parse::<f64>()
+                    .map(Value::Float)
+                    .map_err(|_e| BuiltinError::Other(format!("failed to parse {}", s)))
+            }
         }
-        Ok(StackObject::Int(
-            args[0]
-                .unwrap_any_str()
-                .unwrap()
-                .parse::<i64>()
-                .map_err(|_e| BuiltinError::Other(format!("failed to parse {}", args[0])))?,
-        ))
     });
 
     builtin!("list", AtLeast(0), |mut args, _vm| {
         Ok(args.pop().unwrap())
     });
 
+    //range(stop), range(start, stop) or range(start, stop, step)
+    builtin!("range", AtLeast(1), |args, vm| {
+        let bounds = std::iter::once(&args[0])
+            .chain(args[1].unwrap_vector().unwrap().iter())
+            .map(|bound| {
+                bound.unwrap_int().ok_or_else(|| {
+                    BuiltinError::Other(if bound.is_integer() {
+                        format!("range bound {} does not fit into 64 bits", bound)
+                    } else {
+                        format!("range expected Int, got {}", bound.type_string())
+                    })
+                })
+            })
+            .collect::<std::result::Result<Vec<i64>, BuiltinError>>()?;
+
+        let (start, stop, step) = match *bounds.as_slice() {
+            [stop] => (0, stop, 1),
+            [start, stop] => (start, stop, 1),
+            [start, stop, step] => (start, stop, step),
+            _ => return Err(format!("range expected at most 3 args, got {}", bounds.len()).into()),
+        };
+
+        let range = Range::new(start, stop, step)
+            .ok_or_else(|| BuiltinError::Other("range step cannot be zero".to_string()))?;
+        Ok(vm.gc.store(range))
+    });
+
+    builtin!("iter", Exact(1), |mut args, vm| {
+        vm.make_iterator(args.pop().unwrap())
+    });
+
+    //returns `Nothing` once iterator is exhausted
+    builtin!("next", Exact(1), |args, vm| {
+        Ok(vm.iterator_next(&args[0])?.unwrap_or_default())
+    });
+
     builtin!("arity", Exact(1), |args, vm| {
         args[0]
             .get_arity(vm)
@@ -260,7 +453,7 @@ pub fn builtin_factory() -> BuiltinMap {
 
     builtin!("is_vararg", Exact(1), |args, vm| {
         let v = args
-            .get(0)
+            .first()
             .unwrap()
             .get_arity(vm)
             .unwrap_or(Arity::Exact(0))
@@ -269,6 +462,55 @@ pub fn builtin_factory() -> BuiltinMap {
         Ok(v.into())
     });
 
+    //recompiles imported module from source, live instances of its structs get new methods
+    //unless fields of the struct changed
+    builtin!("reload", Exact(1), |mut args, vm| {
+        let module = match args[0].as_heap_object() {
+            Some(OwnedObjectItem::Module(module)) => module.clone(),
+            _ => {
+                return Err(BuiltinError::Other(format!(
+                    "reload expected Module, got {}",
+                    args[0].type_string()
+                )))
+            }
+        };
+        vm.reload_module(&module, true).map_err(|e| match e {
+            //errors raised by module code (including `exit`) pass through unchanged
+            ModuleError::Runtime(e, _) => BuiltinError::Interpret(e),
+            ModuleError::Load(message) => BuiltinError::Other(message),
+        })?;
+        Ok(args.pop().unwrap())
+    });
+
+    //arguments passed to script after its filename
+    builtin!("args", Exact(0), |_args, vm| {
+        let args = vm.script_args.clone();
+        Ok(crate::data::conversion::to_value(&args, vm.gc).unwrap())
+    });
+
+    //value of environment variable, `Nothing` if it is not set
+    builtin!("env", Exact(1), |args, vm| {
+        let name = string_arg(&args[0], "env")?;
+        Ok(std::env::var(name)
+            .map(|value| vm.gc.new_string(&value))
+            .unwrap_or(Value::Nothing))
+    });
+
+    //stops script, interpreter exits with given code
+    builtin!("exit", Exact(1), |args, _vm| {
+        let code = args[0]
+            .unwrap_int()
+            .and_then(|code| i32::try_from(code).ok())
+            .ok_or_else(|| {
+                BuiltinError::Other(if args[0].is_integer() {
+                    format!("exit code {} does not fit into 32 bits", args[0])
+                } else {
+                    format!("exit expected Int, got {}", args[0].type_string())
+                })
+            })?;
+        Err(BuiltinError::Exit(code))
+    });
+
     builtin!("print", AtLeast(0), |args, vm| {
         let values: &mut VVec = args[0].unwrap_vector().unwrap();
 
@@ -293,20 +535,690 @@ pub fn builtin_factory() -> BuiltinMap {
         }
     });
 
+    //methods of `Int` receive both small integers and bignums
     methods!("Int",
+        "abs" => Exact(0) => |obj, _args, context| {
+            match obj.unwrap_int().and_then(i64::checked_abs) {
+                Some(n) => Ok(Value::Int(n)),
+                None => Ok(normalize_bigint(to_bigint(&obj).unwrap().abs(), context.gc)),
+            }
+        };
+        "_mod" => Exact(1) => |obj, args, context| {
+            match &args[0] {
+                Value::Int(0) => Err("modulo by zero".to_string().into()),
+                b if b.is_integer() => {
+                    Ok(integer_binary(&obj, b, IntegerOp::Rem, context.gc).unwrap())
+                }
+                _ => Err("expected int".to_string().into())
+            }
+        }
+
+    );
+
+    methods!("Float",
         "abs" => Exact(0) => |obj, _args, _context| {
-            Ok(Value::Int(obj.unwrap_int().unwrap().abs()))
+            Ok(Value::Float(obj.unwrap_float().unwrap().abs()))
+        };
+        "is_nan" => Exact(0) => |obj, _args, _context| {
+            Ok(obj.unwrap_float().unwrap().is_nan().into())
+        };
+        "is_infinite" => Exact(0) => |obj, _args, _context| {
+            Ok(obj.unwrap_float().unwrap().is_infinite().into())
+        };
+        //round(digits) keeps given number of digits after decimal point, default is 0
+        "round" => AtLeast(0) => |obj, args, _context| {
+            let f = obj.unwrap_float().unwrap();
+            let digits = match args[0].unwrap_vector().unwrap().as_slice() {
+                [] => 0,
+                &[Value::Int(digits)] => digits,
+                [other] => {
+                    return Err(format!("round expected Int digits, got {}", other.type_string()).into())
+                }
+                rest => return Err(format!("round expected at most 1 arg, got {}", rest.len()).into()),
+            };
+            let scale = 10f64.powi(digits.clamp(-308, 308) as i32);
+            let rounded = (f * scale).round() / scale;
+            //scaling may overflow for huge numbers, which are already integral
+            Ok(Value::Float(if rounded.is_finite() { rounded } else { f }))
+        }
+    );
+
+    //all string representations (short strings and heap strings) report type `String`.
+    //Indices and lengths are measured in unicode scalar values, not bytes
+    methods!("String",
+        "len" => Exact(0) => |obj, _args, _context| {
+            Ok(Value::Int(string_arg(&obj, "len")?.chars().count() as i64))
+        };
+        "slice" => Exact(2) => |obj, args, context| {
+            let s = string_arg(&obj, "slice")?;
+            let length = s.chars().count() as i64;
+            let resolve = |index: &Value, default: i64| match *index {
+                Value::Nothing => Ok(default),
+                Value::Int(i) if i < 0 => Ok((length + i).max(0)),
+                Value::Int(i) => Ok(i.min(length)),
+                ref other => Err(BuiltinError::Other(format!(
+                    "slice expected Int or Nothing as index, got {}",
+                    other.type_string()
+                ))),
+            };
+            let start = resolve(&args[0], 0)? as usize;
+            let end = resolve(&args[1], length)? as usize;
+            let slice = s
+                .chars()
+                .skip(start)
+                .take(end.saturating_sub(start))
+                .collect::<String>();
+            Ok(context.gc.new_string(&slice))
+        };
+        "split" => Exact(1) => |obj, args, context| {
+            let s = string_arg(&obj, "split")?;
+            let separator = string_arg(&args[0], "split")?;
+            if separator.is_empty() {
+                return Err("split separator should not be empty".to_string().into());
+            }
+            let parts = s
+                .split(separator)
+                .map(|part| context.gc.new_string(part))
+                .collect::<VVec>();
+            Ok(context.gc.store(parts))
         };
-        "_mod" => Exact(1) => |obj, args, _context| {
+        "join" => Exact(1) => |obj, args, context| {
+            let separator = string_arg(&obj, "join")?;
+            let items = args[0].unwrap_vector().ok_or_else(|| {
+                BuiltinError::Other(format!(
+                    "join expected Vector, got {}",
+                    args[0].type_string()
+                ))
+            })?;
+            let mut parts = Vec::with_capacity(items.len());
+            for (idx, item) in items.iter().enumerate() {
+                parts.push(item.unwrap_any_str().ok_or_else(|| {
+                    BuiltinError::Other(format!(
+                        "join expected all items to be strings, got {} at {}",
+                        item.type_string(),
+                        idx
+                    ))
+                })?);
+            }
+            let joined = parts.join(separator);
+            Ok(context.gc.new_string(&joined))
+        };
+        "trim" => Exact(0) => |obj, _args, context| {
+            let trimmed = string_arg(&obj, "trim")?.trim();
+            Ok(context.gc.new_string(trimmed))
+        };
+        "find" => Exact(1) => |obj, args, _context| {
+            let s = string_arg(&obj, "find")?;
+            let pattern = string_arg(&args[0], "find")?;
+            Ok(s.find(pattern)
+                .map(|byte_idx| Value::Int(s[..byte_idx].chars().count() as i64))
+                .unwrap_or(Value::Nothing))
+        };
+        "replace" => Exact(2) => |obj, args, context| {
+            let s = string_arg(&obj, "replace")?;
+            let from = string_arg(&args[0], "replace")?;
+            let to = string_arg(&args[1], "replace")?;
+            let replaced = s.replace(from, to);
+            Ok(context.gc.new_string(&replaced))
+        };
+        "starts_with" => Exact(1) => |obj, args, _context| {
+            let s = string_arg(&obj, "starts_with")?;
+            Ok(s.starts_with(string_arg(&args[0], "starts_with")?).into())
+        };
+        "ends_with" => Exact(1) => |obj, args, _context| {
+            let s = string_arg(&obj, "ends_with")?;
+            Ok(s.ends_with(string_arg(&args[0], "ends_with")?).into())
+        };
+        "upper" => Exact(0) => |obj, _args, context| {
+            let upper = string_arg(&obj, "upper")?.to_uppercase();
+            Ok(context.gc.new_string(&upper))
+        };
+        "lower" => Exact(0) => |obj, _args, context| {
+            let lower = string_arg(&obj, "lower")?.to_lowercase();
+            Ok(context.gc.new_string(&lower))
+        };
+        "chars" => Exact(0) => |obj, _args, context| {
+            let chars = string_arg(&obj, "chars")?
+                .chars()
+                .map(|c| context.gc.new_string(c.encode_utf8(&mut [0; 4])))
+                .collect::<VVec>();
+            Ok(context.gc.store(chars))
+        };
+        "repeat" => Exact(1) => |obj, args, context| {
+            let s = string_arg(&obj, "repeat")?;
             match args[0] {
-                Value::Int(b) => {
-                    Ok(Value::Int(obj.unwrap_int().unwrap() % b ))
+                Value::Int(n) if n >= 0 => {
+                    let too_large = || format!("repeat count {} is too large", n);
+                    let len = s
+                        .len()
+                        .checked_mul(n as usize)
+                        .filter(|&len| len <= MAX_REPEATED_LEN)
+                        .ok_or_else(too_large)?;
+                    if len == 0 {
+                        return Ok(context.gc.new_string(""));
+                    }
+                    let mut repeated = String::new();
+                    repeated.try_reserve_exact(len).map_err(|_| too_large())?;
+                    for _ in 0..n {
+                        repeated.push_str(s);
+                    }
+                    Ok(context.gc.new_string(&repeated))
+                }
+                ref other => Err(format!(
+                    "repeat expected non-negative Int, got {}",
+                    other
+                ).into()),
+            }
+        };
+        "format" => AtLeast(0) => |obj, args, context| {
+            let template = string_arg(&obj, "format")?;
+            let values = args[0].unwrap_vector().unwrap();
+            let formatted = format_template(template, values, context)?;
+            Ok(context.gc.new_string(&formatted))
+        }
+    );
+
+    //push, pop, insert and remove modify vector in place, other methods return new vectors.
+    //Higher-order methods iterate over a snapshot, so callbacks may safely modify the vector
+    methods!("Vector",
+        "len" => Exact(0) => |obj, _args, _context| {
+            Ok(Value::Int(vector_arg(&obj, "len")?.len() as i64))
+        };
+        "get" => Exact(1) => |obj, args, _context| {
+            let items = vector_arg(&obj, "get")?;
+            let idx = vector_index(&args[0], items.len(), false)?;
+            Ok(items[idx].clone())
+        };
+        "push" => Exact(1) => |obj, mut args, _context| {
+            vector_arg(&obj, "push")?.push(args.pop().unwrap());
+            Ok(Value::Nothing)
+        };
+        "pop" => Exact(0) => |obj, _args, _context| {
+            vector_arg(&obj, "pop")?
+                .pop()
+                .ok_or_else(|| "pop from empty vector".to_string().into())
+        };
+        "insert" => Exact(2) => |obj, mut args, _context| {
+            let items = vector_arg(&obj, "insert")?;
+            let idx = vector_index(&args[0], items.len(), true)?;
+            items.insert(idx, args.pop().unwrap());
+            Ok(Value::Nothing)
+        };
+        "remove" => Exact(1) => |obj, args, _context| {
+            let items = vector_arg(&obj, "remove")?;
+            let idx = vector_index(&args[0], items.len(), false)?;
+            Ok(items.remove(idx))
+        };
+        "map" => Exact(1) => |obj, args, context| {
+            let items = vector_arg(&obj, "map")?.clone();
+            let mut result = VVec::with_capacity(items.len());
+            for item in items {
+                result.push(context.call(args[0].clone(), vec![item])?);
+            }
+            Ok(context.gc.store(result))
+        };
+        "filter" => Exact(1) => |obj, args, context| {
+            let items = vector_arg(&obj, "filter")?.clone();
+            let mut result = VVec::new();
+            for item in items {
+                if context.call(args[0].clone(), vec![item.clone()])?.as_bool() {
+                    result.push(item);
+                }
+            }
+            Ok(context.gc.store(result))
+        };
+        "fold" => Exact(2) => |obj, args, context| {
+            let items = vector_arg(&obj, "fold")?.clone();
+            let mut accumulator = args[0].clone();
+            for item in items {
+                accumulator = context.call(args[1].clone(), vec![accumulator, item])?;
+            }
+            Ok(accumulator)
+        };
+        "reduce" => Exact(1) => |obj, args, context| {
+            let mut items = vector_arg(&obj, "reduce")?.clone().into_iter();
+            let mut accumulator = items
+                .next()
+                .ok_or_else(|| BuiltinError::Other("reduce of empty vector".to_string()))?;
+            for item in items {
+                accumulator = context.call(args[0].clone(), vec![accumulator, item])?;
+            }
+            Ok(accumulator)
+        };
+        "sort" => AtLeast(0) => |obj, args, context| {
+            let mut items = vector_arg(&obj, "sort")?.clone();
+            match args[0].unwrap_vector().unwrap().clone().as_slice() {
+                [] => try_sort_by(&mut items, compare_values)?,
+
+                [f] if f.get_arity(context).map(|a| a.accepts(2)).unwrap_or(false) => {
+                    try_sort_by(&mut items, |a, b| {
+                        match context.call(f.clone(), vec![a.clone(), b.clone()])? {
+                            Value::Int(n) => Ok(n.cmp(&0)),
+                            other => Err(format!(
+                                "sort comparator should return Int, got {}",
+                                other.type_string()
+                            ).into()),
+                        }
+                    })?
+                }
+
+                [f] if f.get_arity(context).map(|a| a.accepts(1)).unwrap_or(false) => {
+                    let mut keyed = Vec::with_capacity(items.len());
+                    for item in items {
+                        keyed.push((context.call(f.clone(), vec![item.clone()])?, item));
+                    }
+                    try_sort_by(&mut keyed, |(k1, _), (k2, _)| compare_values(k1, k2))?;
+                    items = keyed.into_iter().map(|(_, item)| item).collect();
+                }
+
+                [other] => {
+                    return Err(format!(
+                        "sort expected key function or comparator, got {}",
+                        other.type_string()
+                    ).into())
+                }
+
+                more => {
+                    return Err(BuiltinError::ArityMismatch {
+                        provided: more.len(),
+                        expected: Exact(1),
+                    })
                 }
-                _ => Err("expected int".to_string().into())
             }
+            Ok(context.gc.store(items))
+        };
+        "reverse" => Exact(0) => |obj, _args, context| {
+            let mut items = vector_arg(&obj, "reverse")?.clone();
+            items.reverse();
+            Ok(context.gc.store(items))
+        };
+        "contains" => Exact(1) => |obj, args, _context| {
+            let items = vector_arg(&obj, "contains")?;
+            Ok(items.iter().any(|item| equality_operator(item, &args[0])).into())
+        };
+        "index_of" => Exact(1) => |obj, args, _context| {
+            let items = vector_arg(&obj, "index_of")?;
+            Ok(items
+                .iter()
+                .position(|item| equality_operator(item, &args[0]))
+                .map(|idx| Value::Int(idx as i64))
+                .unwrap_or(Value::Nothing))
+        };
+        "concat" => Exact(1) => |obj, args, context| {
+            let mut items = vector_arg(&obj, "concat")?.clone();
+            items.extend(vector_arg(&args[0], "concat")?.iter().cloned());
+            Ok(context.gc.store(items))
+        };
+        "zip" => Exact(1) => |obj, args, context| {
+            let items = vector_arg(&obj, "zip")?.clone();
+            let others = vector_arg(&args[0], "zip")?.clone();
+            let pairs = items
+                .into_iter()
+                .zip(others)
+                .map(|(a, b)| context.gc.store(vec![a, b]))
+                .collect::<VVec>();
+            Ok(context.gc.store(pairs))
+        };
+        "enumerate" => Exact(0) => |obj, _args, context| {
+            let items = vector_arg(&obj, "enumerate")?.clone();
+            let pairs = items
+                .into_iter()
+                .enumerate()
+                .map(|(idx, item)| context.gc.store(vec![Value::Int(idx as i64), item]))
+                .collect::<VVec>();
+            Ok(context.gc.store(pairs))
+        }
+    );
+
+    methods!("Range",
+        "len" => Exact(0) => |obj, _args, _context| {
+            Ok(Value::Int(obj.unwrap_range().unwrap().len() as i64))
+        };
+        "contains" => Exact(1) => |obj, args, _context| {
+            let range = obj.unwrap_range().unwrap();
+            Ok(args[0].unwrap_int().is_some_and(|n| range.contains(n)).into())
+        };
+        "iter" => Exact(0) => |obj, _args, context| {
+            context.make_iterator(obj)
         }
+    );
 
+    //adapters are lazy, they pull items from underlying iterator only when asked for next item
+    methods!("Iterator",
+        "next" => Exact(0) => |obj, _args, context| {
+            Ok(context.iterator_next(&obj)?.unwrap_or_default())
+        };
+        "map" => Exact(1) => |obj, mut args, context| {
+            let function = args.pop().unwrap();
+            Ok(context.gc.store(NativeIterator::Map { inner: obj, function }))
+        };
+        "filter" => Exact(1) => |obj, mut args, context| {
+            let predicate = args.pop().unwrap();
+            Ok(context.gc.store(NativeIterator::Filter { inner: obj, predicate }))
+        };
+        "take" => Exact(1) => |obj, args, context| {
+            let remaining = match args[0] {
+                Value::Int(n) if n >= 0 => n as usize,
+                ref other => {
+                    return Err(format!(
+                        "take expected non-negative Int, got {}",
+                        crate::data::objects::pretty_format(other, context)
+                    ).into())
+                }
+            };
+            Ok(context.gc.store(NativeIterator::Take { inner: obj, remaining }))
+        };
+        "enumerate" => Exact(0) => |obj, _args, context| {
+            Ok(context.gc.store(NativeIterator::Enumerate { inner: obj, position: 0 }))
+        };
+        "fold" => Exact(2) => |obj, args, context| {
+            let mut accumulator = args[0].clone();
+            while let Some(item) = context.iterator_next(&obj)? {
+                accumulator = context.call(args[1].clone(), vec![accumulator, item])?;
+            }
+            Ok(accumulator)
+        };
+        "collect" => Exact(0) => |obj, _args, context| {
+            let mut items = VVec::new();
+            while let Some(item) = context.iterator_next(&obj)? {
+                items.push(item);
+            }
+            Ok(context.gc.store(items))
+        }
     );
 
+    map.add_module(super::json::json_module());
+    map.add_module(super::math::math_module());
+
     map
 }
+
+fn string_arg<'a>(value: &'a Value, method: &str) -> std::result::Result<&'a str, BuiltinError> {
+    value.unwrap_any_str().ok_or_else(|| {
+        BuiltinError::Other(format!(
+            "{} expected String, got {}",
+            method,
+            value.type_string()
+        ))
+    })
+}
+
+fn vector_arg<'a>(
+    value: &'a Value,
+    method: &str,
+) -> std::result::Result<&'a mut VVec, BuiltinError> {
+    value.unwrap_vector().ok_or_else(|| {
+        BuiltinError::Other(format!(
+            "{} expected Vector, got {}",
+            method,
+            value.type_string()
+        ))
+    })
+}
+
+/// converts (possibly negative) index into position inside vector of length `len`.
+/// `allow_end` permits index equal to length (position after last element)
+fn vector_index(
+    index: &Value,
+    len: usize,
+    allow_end: bool,
+) -> std::result::Result<usize, BuiltinError> {
+    let idx = index.unwrap_int().ok_or_else(|| {
+        BuiltinError::Other(if index.is_integer() {
+            format!(
+                "index {} is out of bounds for vector of length {}",
+                index, len
+            )
+        } else {
+            format!("expected Int as index, got {}", index.type_string())
+        })
+    })?;
+    let resolved = if idx < 0 { idx + len as i64 } else { idx };
+    let upper_bound = if allow_end {
+        len as i64
+    } else {
+        len as i64 - 1
+    };
+    if resolved < 0 || resolved > upper_bound {
+        return Err(format!(
+            "index {} is out of bounds for vector of length {}",
+            idx, len
+        )
+        .into());
+    }
+    Ok(resolved as usize)
+}
+
+pub(super) fn compare_values(a: &Value, b: &Value) -> std::result::Result<Ordering, BuiltinError> {
+    a.partial_cmp(b)
+        .or_else(|| match (numeric_cast(a), numeric_cast(b)) {
+            (Some(a), Some(b)) => a.downgrade().partial_cmp(&b.downgrade()),
+            _ => None,
+        })
+        .ok_or_else(|| {
+            BuiltinError::Other(format!(
+                "cannot compare {} and {}",
+                a.type_string(),
+                b.type_string()
+            ))
+        })
+}
+
+/// stable sort with fallible comparison, first error is returned after sorting
+fn try_sort_by<T>(
+    items: &mut [T],
+    mut compare: impl FnMut(&T, &T) -> std::result::Result<Ordering, BuiltinError>,
+) -> std::result::Result<(), BuiltinError> {
+    let mut error = None;
+    items.sort_by(|a, b| {
+        if error.is_some() {
+            return Ordering::Equal;
+        }
+        compare(a, b).unwrap_or_else(|e| {
+            error = Some(e);
+            Ordering::Equal
+        })
+    });
+    error.map_or(Ok(()), Err)
+}
+
+/// substitutes `{}` (next argument) and `{N}` (argument by index) placeholders,
+/// `{{` and `}}` are used to write braces themselves
+fn format_template(
+    template: &str,
+    values: &[Value],
+    vm: &VM,
+) -> std::result::Result<String, BuiltinError> {
+    let mut result = String::with_capacity(template.len());
+    let mut next_value = 0;
+    let mut chars = template.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.as_str().starts_with('{') => {
+                chars.next();
+                result.push('{');
+            }
+            '}' if chars.as_str().starts_with('}') => {
+                chars.next();
+                result.push('}');
+            }
+            '{' => {
+                let rest = chars.as_str();
+                let closing = rest
+                    .find('}')
+                    .ok_or_else(|| "format: unclosed placeholder".to_string())?;
+                let placeholder = &rest[..closing];
+                let idx = if placeholder.is_empty() {
+                    next_value += 1;
+                    next_value - 1
+                } else {
+                    placeholder
+                        .parse::<usize>()
+                        .map_err(|_| format!("format: invalid placeholder {{{}}}", placeholder))?
+                };
+                let value = values.get(idx).ok_or_else(|| {
+                    format!(
+                        "format: placeholder refers to argument {} but only {} provided",
+                        idx,
+                        values.len()
+                    )
+                })?;
+                result.push_str(&crate::data::objects::pretty_format(value, vm));
+                chars = rest[closing + 1..].chars();
+            }
+            '}' => return Err("format: unmatched `}`".to_string().into()),
+            c => result.push(c),
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::Cell, rc::Rc};
+
+    use super::{builtin_factory, NativeModule};
+    use crate::data::gc::GC;
+    use crate::data::objects::Value;
+    use crate::execution::arity::Arity;
+    use crate::test::{assert_errors, run_program, with_builtins, with_vm};
+
+    #[test]
+    fn native_modules_should_be_importable() {
+        let counter = Rc::new(Cell::new(0));
+
+        let mut module = NativeModule::new("host.counter");
+        let state = counter.clone();
+        module.add_function("next", Arity::Exact(0), move |_args, _vm| {
+            state.set(state.get() + 1);
+            Ok(Value::Int(state.get()))
+        });
+        module.add_value("start", Value::Int(0)).unwrap();
+
+        let mut builtins = builtin_factory();
+        builtins.add_module(module);
+
+        let program = "
+import host.counter.next
+import host.counter.start
+import host.counter.next as next2
+assert start == 0
+assert next() == 1
+assert next2() == 2
+next
+";
+        with_builtins(&builtins, |vm| {
+            let next = run_program(vm, program).unwrap();
+            assert_eq!(
+                crate::data::objects::pretty_format(&next, vm),
+                "host.counter.next"
+            );
+        });
+
+        assert_eq!(counter.get(), 2);
+        //qualified names do not leak into globals
+        assert!(builtins.get_builtin("host.counter.next").is_none());
+    }
+
+    #[test]
+    fn heap_values_should_not_be_added_to_builtins() {
+        let mut gc = unsafe { GC::default_gc() };
+        let value = gc.new_string("string that lives on heap");
+
+        let mut module = NativeModule::new("host.values");
+        assert_eq!(
+            module.add_value("greeting", value.clone()),
+            Err("builtin value greeting should not be heap-allocated, got String".to_string())
+        );
+        let mut builtins = builtin_factory();
+        assert!(builtins.add_value("greeting", value).is_err());
+        assert!(builtins.get_builtin("greeting").is_none());
+    }
+
+    #[test]
+    fn native_methods_should_bind_to_structs() {
+        let mut builtins = builtin_factory();
+        builtins.add_method("Point", "norm1", Arity::Exact(1), |obj, _args, _vm| {
+            let instance = obj.unwrap_struct_instance().unwrap();
+            let x = instance.get_field("x").unwrap().unwrap_int().unwrap();
+            let y = instance.get_field("y").unwrap().unwrap_int().unwrap();
+            Ok(Value::Int(x.abs() + y.abs()))
+        });
+        builtins.add_method("Int", "double", Arity::Exact(1), |obj, _args, _vm| {
+            Ok(Value::Int(obj.unwrap_int().unwrap() * 2))
+        });
+
+        let program = "
+struct Point:
+    x
+    y
+assert Point(3, 0-4).norm1() == 7
+assert (21).double() == 42
+";
+        with_builtins(&builtins, |vm| {
+            run_program(vm, program).unwrap();
+        });
+    }
+
+    #[test]
+    fn numeric_conversions_should_report_errors() {
+        let cases = [
+            ("int(list())", "expected string-like or number in int"),
+            ("int(float(\"inf\"))", "int: cannot convert inf to Int"),
+            ("float(\"abc\")", "failed to parse abc"),
+            (
+                "float(true)",
+                "expected string-like or number in float, got Bool",
+            ),
+            (
+                "(1.5).round(\"a\")",
+                "round expected Int digits, got String",
+            ),
+        ];
+        assert_errors(cases);
+    }
+
+    #[test]
+    fn string_methods_should_report_errors() {
+        let cases = [
+            (
+                "\"ab\".repeat(4611686018427387904)",
+                "repeat count 4611686018427387904 is too large",
+            ),
+            (
+                "\"ab\".repeat(10 ** 15)",
+                "repeat count 1000000000000000 is too large",
+            ),
+            (
+                "\"ab\".repeat(-1)",
+                "repeat expected non-negative Int, got -1",
+            ),
+        ];
+        assert_errors(cases);
+
+        //empty string may be repeated any number of times
+        with_vm(|vm| {
+            run_program(vm, "assert \"\".repeat(4611686018427387904) == \"\"").unwrap();
+        });
+    }
+
+    #[test]
+    fn vector_methods_should_report_errors() {
+        let cases = [
+            ("list().pop()", "pop from empty vector"),
+            ("list().reduce((a, b) => a)", "reduce of empty vector"),
+            (
+                "list(1, 2).get(2)",
+                "index 2 is out of bounds for vector of length 2",
+            ),
+            ("list(1, \"a\").sort()", "cannot compare String and Int"),
+            (
+                "list(1, 2).sort((a, b) => true)",
+                "sort comparator should return Int, got Bool",
+            ),
+        ];
+        assert_errors(cases);
+    }
+}
@@ -15,6 +15,8 @@ pub struct Chunk {
     pub name: Token,
     pub module: Module,
     pub arity: Arity,
+    /// calling generator function returns suspended frame instead of running the body
+    pub is_generator: bool,
     pub opcode_to_line: Vec<usize>,
 }
 
@@ -52,6 +54,19 @@ pub enum Opcode {
     Mul,
     Mod,
     Power,
+    /// division rounding towards negative infinity
+    FloorDiv,
+    BitAnd,
+    BitOr,
+    BitXor,
+    BitNot,
+    /// arithmetic negation of `Int` or `Float`
+    Negate,
+    ShiftLeft,
+    /// arithmetic shift, i.e. rounds towards negative infinity
+    ShiftRight,
+    /// converts value on top of stack into string (as `print` would display it)
+    FormatString,
 
     TestEquals,
     TestNotEquals,
@@ -69,14 +84,30 @@ pub enum Opcode {
     JumpIfTrueOrPop(u16),
     JumpRelative(u16),
     JumpAbsolute(u16),
+    /// jumps backwards by given number of instructions
+    JumpBack(u16),
     Pop(u16),
 
+    /// replaces value on top of stack with iterator over it
+    GetIter,
+    /// pushes next item of iterator on top of stack, or pops exhausted iterator and jumps
+    /// forward by given number of instructions
+    ForIter(u16),
+    /// suspends generator frame, passing value on top of stack to its consumer
+    Yield,
+
     Call(u16),
 
     MakeList(u16),
     Return,
 
+    /// pushes global `import_names[idx].1` of module `import_names[idx].0`, or the submodule of
+    /// that name if module has no such global
     Import(u16),
+    /// pushes module `import_names[idx].0` itself
+    ImportModule(u16),
+    /// copies every global of module `import_names[idx].0` into globals of current module
+    ImportAll(u16),
 
     Nop,
     Assert, //SwapStack(u8, u8),
@@ -100,6 +131,7 @@ impl Chunk {
             name,
             module,
             arity,
+            is_generator: false,
             opcode_to_line: vec![],
         }
     }
@@ -219,6 +251,10 @@ mod chunk_pretty_printer {
 
                     Opcode::JumpAbsolute(idx) => pretty_argument!(*idx as usize),
 
+                    Opcode::JumpBack(delta) => pretty_argument!(i - *delta as usize),
+
+                    Opcode::ForIter(delta) => pretty_argument!(i + *delta as usize),
+
                     any_other => {
                         format!("{}", any_other)
                     }
@@ -246,12 +282,19 @@ mod chunk_pretty_printer {
             .filter_map(|(pos, opcode)| match opcode {
                 Opcode::JumpIfFalseOrPop(delta)
                 | Opcode::JumpIfTrueOrPop(delta)
-                | Opcode::JumpRelative(delta) => {
+                | Opcode::JumpRelative(delta)
+                | Opcode::ForIter(delta) => {
                     let start = pos;
                     let end = start + *delta as usize;
                     Some((start, end))
                 }
 
+                Opcode::JumpBack(delta) => {
+                    let start = pos;
+                    let end = start - *delta as usize;
+                    Some((start, end))
+                }
+
                 Opcode::JumpAbsolute(idx) => {
                     let start = pos;
                     let end = *idx as usize;
@@ -0,0 +1,290 @@
+use crate::data::objects::{NativeIterator, Value};
+
+use super::builtins::BuiltinError;
+use super::vm::VM;
+
+type Result<T> = std::result::Result<T, BuiltinError>;
+
+/// iterator protocol shared by `for` loops, iterator builtins and methods.
+///
+/// Iterable values are ranges, vectors, strings (iterated by characters), maps (iterated by keys)
+/// and user structs. Struct is iterable if it implements `iter` (which should return another
+/// iterable) or if it is an iterator itself, i.e. implements `next` which returns `Nothing`
+/// when there are no more items
+impl<'gc, 'builtins> VM<'gc, 'builtins> {
+    /// wraps iterable value into iterator object. Iterators are returned as is
+    pub fn make_iterator(&mut self, value: Value) -> Result<Value> {
+        if value.unwrap_iterator().is_some() {
+            return Ok(value);
+        }
+
+        let iterator = if let Some(range) = value.unwrap_range() {
+            NativeIterator::Range {
+                range: *range,
+                position: 0,
+            }
+        } else if value.unwrap_vector().is_some() {
+            NativeIterator::Vector {
+                source: value,
+                position: 0,
+            }
+        } else if value.unwrap_any_str().is_some() {
+            NativeIterator::Chars {
+                source: value,
+                position: 0,
+            }
+        } else if let Some(map) = value.unwrap_map() {
+            NativeIterator::Keys {
+                keys: map.keys().cloned().collect(),
+                position: 0,
+            }
+        } else if value.unwrap_struct_instance().is_some() {
+            if let Some(iter_method) = value.lookup("iter", self) {
+                let produced = self.call(iter_method, vec![])?;
+                if produced.unwrap_struct_instance().is_some() {
+                    //do not call `iter` again, as it may return struct itself
+                    self.protocol_iterator(produced)?
+                } else {
+                    return self.make_iterator(produced);
+                }
+            } else {
+                self.protocol_iterator(value)?
+            }
+        } else {
+            return Err(format!("{} is not iterable", value.type_string()).into());
+        };
+
+        Ok(self.gc.store(iterator))
+    }
+
+    fn protocol_iterator(&mut self, value: Value) -> Result<NativeIterator> {
+        match value.lookup("next", self) {
+            Some(_) => Ok(NativeIterator::Protocol(value)),
+            None => Err(format!(
+                "{} is not iterable (it should implement `iter` or `next`)",
+                value.unwrap_struct_instance().map_or_else(
+                    || value.type_string().to_string(),
+                    |instance| instance
+                        .descriptor
+                        .unwrap_struct_descriptor()
+                        .unwrap()
+                        .name
+                        .clone()
+                )
+            )
+            .into()),
+        }
+    }
+
+    /// advances iterator, returning `None` once it is exhausted
+    pub fn iterator_next(&mut self, iterator: &Value) -> Result<Option<Value>> {
+        let state = iterator.unwrap_iterator().ok_or_else(|| {
+            BuiltinError::Other(format!("expected Iterator, got {}", iterator.type_string()))
+        })?;
+
+        let next = match state {
+            NativeIterator::Range { range, position } => {
+                let item = range.get(*position).map(Value::Int);
+                *position += 1;
+                item
+            }
+
+            NativeIterator::Vector { source, position } => {
+                //vector is not copied, so items pushed during iteration are visited as well
+                let item = source.unwrap_vector().unwrap().get(*position).cloned();
+                *position += 1;
+                item
+            }
+
+            NativeIterator::Chars { source, position } => {
+                let s = source.unwrap_any_str().unwrap();
+                match s[*position..].chars().next() {
+                    Some(c) => {
+                        *position += c.len_utf8();
+                        Some(self.gc.new_string(c.encode_utf8(&mut [0; 4])))
+                    }
+                    None => None,
+                }
+            }
+
+            NativeIterator::Keys { keys, position } => {
+                let item = keys.get(*position).cloned();
+                *position += 1;
+                item
+            }
+
+            NativeIterator::Protocol(object) => {
+                let object = object.clone();
+                let next_method = object.lookup("next", self).ok_or_else(|| {
+                    BuiltinError::Other(format!(
+                        "iterator {} has no method `next`",
+                        object.type_string()
+                    ))
+                })?;
+                match self.call(next_method, vec![])? {
+                    Value::Nothing => None,
+                    item => Some(item),
+                }
+            }
+
+            NativeIterator::Map { inner, function } => {
+                let (inner, function) = (inner.clone(), function.clone());
+                match self.iterator_next(&inner)? {
+                    Some(item) => Some(self.call(function, vec![item])?),
+                    None => None,
+                }
+            }
+
+            NativeIterator::Filter { inner, predicate } => {
+                let (inner, predicate) = (inner.clone(), predicate.clone());
+                loop {
+                    match self.iterator_next(&inner)? {
+                        Some(item) => {
+                            if self.call(predicate.clone(), vec![item.clone()])?.as_bool() {
+                                break Some(item);
+                            }
+                        }
+                        None => break None,
+                    }
+                }
+            }
+
+            NativeIterator::Take { inner, remaining } => {
+                if *remaining == 0 {
+                    None
+                } else {
+                    *remaining -= 1;
+                    let inner = inner.clone();
+                    self.iterator_next(&inner)?
+                }
+            }
+
+            NativeIterator::Enumerate { inner, position } => {
+                let idx = *position as i64;
+                *position += 1;
+                let inner = inner.clone();
+                self.iterator_next(&inner)?
+                    .map(|item| self.gc.store(vec![Value::Int(idx), item]))
+            }
+
+            NativeIterator::Generator { .. } => {
+                let NativeIterator::Generator {
+                    function,
+                    frame,
+                    ip,
+                } = std::mem::replace(state, NativeIterator::Running)
+                else {
+                    unreachable!()
+                };
+
+                match self.resume_generator(function.clone(), frame, ip) {
+                    Ok(Some((item, frame, ip))) => {
+                        *state = NativeIterator::Generator {
+                            function,
+                            frame,
+                            ip,
+                        };
+                        Some(item)
+                    }
+                    Ok(None) => None,
+                    Err(e) => {
+                        *state = NativeIterator::Exhausted;
+                        return Err(e.into());
+                    }
+                }
+            }
+
+            NativeIterator::Running => {
+                return Err("generator is already running".to_string().into())
+            }
+
+            NativeIterator::Exhausted => None,
+        };
+
+        if next.is_none() {
+            if let Some(state) = iterator.unwrap_iterator() {
+                *state = NativeIterator::Exhausted;
+            }
+        }
+
+        Ok(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::execution::module::compile_program;
+    use crate::test::{run_error, run_program, test_module, with_vm};
+
+    #[test]
+    fn non_iterable_values_should_be_reported() {
+        assert!(run_error("for x in 5\n    pass").contains("Int is not iterable"));
+
+        let program = "
+struct Point:
+    x
+for p in Point(1)
+    pass
+";
+        assert!(run_error(program)
+            .contains("Point is not iterable (it should implement `iter` or `next`)"));
+    }
+
+    #[test]
+    fn errors_inside_loop_body_should_propagate() {
+        let program = "
+def check(x) =
+    assert x < 2
+for i in range(5)
+    check(i)
+";
+        assert!(run_error(program).contains("AssertionFailure"));
+    }
+
+    #[test]
+    fn invalid_ranges_should_be_reported() {
+        assert!(run_error("range(0, 1, 0)").contains("range step cannot be zero"));
+        assert!(run_error("range(1, 2, 3, 4)").contains("range expected at most 3 args, got 4"));
+        assert!(run_error("range(\"a\")").contains("range expected Int, got String"));
+    }
+
+    #[test]
+    fn yield_outside_of_function_should_be_rejected() {
+        with_vm(|vm| {
+            let error = compile_program("yield 1".to_string(), &test_module(), vm).unwrap_err();
+            assert!(error.to_string().contains("yield outside of function"));
+        });
+    }
+
+    #[test]
+    fn generators_should_not_be_resumed_recursively() {
+        let program = "
+var generator = Nothing
+def gen() =
+    yield next(generator)
+generator = gen()
+next(generator)
+";
+        assert!(run_error(program).contains("generator is already running"));
+    }
+
+    #[test]
+    fn failed_generators_should_be_exhausted() {
+        let program = "
+def gen() =
+    yield 1
+    assert false
+    yield 2
+var g = gen()
+next(g)
+";
+        assert!(run_error(&format!("{program}next(g)")).contains("AssertionFailure"));
+
+        with_vm(|vm| {
+            run_program(vm, program).unwrap();
+            let g = vm.get_global(&test_module(), "g").unwrap();
+            assert!(vm.iterator_next(&g).is_err());
+            assert!(matches!(vm.iterator_next(&g), Ok(None)));
+        });
+    }
+}
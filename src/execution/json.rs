@@ -0,0 +1,651 @@
+//! native `json` module: `json.parse(string)` and `json.stringify(value, indent)`
+use std::fmt::Write;
+
+use crate::data::objects::{OwnedObjectItem, StackObject, VMap, VVec, Value};
+
+use super::arity::Arity;
+use super::builtins::{BuiltinError, NativeModule};
+use super::vm::VM;
+
+/// nesting limit for both parsing and stringifying, protects native stack from overflowing
+const MAX_DEPTH: usize = 512;
+
+pub fn json_module() -> NativeModule {
+    let mut module = NativeModule::new("json");
+
+    module.add_function("parse", Arity::Exact(1), |args, vm| {
+        let source = args[0].unwrap_any_str().ok_or_else(|| {
+            BuiltinError::Other(format!(
+                "json.parse expected string, got {}",
+                args[0].type_string()
+            ))
+        })?;
+        Parser::new(source, vm)
+            .parse_document()
+            .map_err(|e| BuiltinError::Other(format!("json.parse: {}", e)))
+    });
+
+    module.add_function("stringify", Arity::AtLeast(1), |args, vm| {
+        let indent = match args[1].unwrap_vector().unwrap().as_slice() {
+            [] | [Value::Nothing] => None,
+            &[Value::Int(n)] if n >= 0 => Some(n as usize),
+            [other] => {
+                return Err(format!(
+                    "json.stringify expected non-negative Int as indent, got {}",
+                    other
+                )
+                .into())
+            }
+            rest => {
+                return Err(BuiltinError::ArityMismatch {
+                    provided: rest.len() + 1,
+                    expected: Arity::Exact(2),
+                })
+            }
+        };
+
+        let mut writer = Writer {
+            output: String::new(),
+            indent,
+            visiting: vec![],
+            path: vec![],
+        };
+        writer
+            .write_value(&args[0], 0)
+            .map_err(|e| BuiltinError::Other(format!("json.stringify: {}", e)))?;
+        Ok(vm.gc.new_string(&writer.output))
+    });
+
+    module
+}
+
+struct Parser<'src, 'vm, 'gc, 'builtins> {
+    source: &'src str,
+    position: usize,
+    vm: &'vm mut VM<'gc, 'builtins>,
+}
+
+impl<'src, 'vm, 'gc, 'builtins> Parser<'src, 'vm, 'gc, 'builtins> {
+    fn new(source: &'src str, vm: &'vm mut VM<'gc, 'builtins>) -> Self {
+        Parser {
+            source,
+            position: 0,
+            vm,
+        }
+    }
+
+    fn error<T>(&self, message: String) -> Result<T, String> {
+        let consumed = &self.source[..self.position];
+        let line = consumed.matches('\n').count() + 1;
+        let column = consumed
+            .rsplit('\n')
+            .next()
+            .map(|s| s.chars().count())
+            .unwrap_or(0)
+            + 1;
+        Err(format!("{} at line {}, column {}", message, line, column))
+    }
+
+    fn unexpected<T>(&self, expected: &str) -> Result<T, String> {
+        match self.peek() {
+            Some(c) => self.error(format!("expected {}, got {:?}", expected, c)),
+            None => self.error(format!("expected {}, got end of input", expected)),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.source[self.position..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += c.len_utf8();
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(' ' | '\t' | '\n' | '\r') = self.peek() {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.peek() == Some(c) {
+            self.position += 1;
+            Ok(())
+        } else {
+            self.unexpected(&format!("{:?}", c))
+        }
+    }
+
+    fn parse_document(&mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+        let value = self.parse_value(0)?;
+        self.skip_whitespace();
+        if self.position < self.source.len() {
+            return self.unexpected("end of input");
+        }
+        Ok(value)
+    }
+
+    fn parse_value(&mut self, depth: usize) -> Result<Value, String> {
+        if depth > MAX_DEPTH {
+            return self.error("document is nested too deeply".to_string());
+        }
+
+        match self.peek() {
+            Some('{') => self.parse_object(depth),
+            Some('[') => self.parse_array(depth),
+            Some('"') => {
+                let s = self.parse_string()?;
+                Ok(self.vm.gc.new_string(&s))
+            }
+            Some('-' | '0'..='9') => self.parse_number(),
+            Some('t') => self.parse_keyword("true", Value::Bool(true)),
+            Some('f') => self.parse_keyword("false", Value::Bool(false)),
+            Some('n') => self.parse_keyword("null", Value::Nothing),
+            _ => self.unexpected("value"),
+        }
+    }
+
+    fn parse_keyword(&mut self, keyword: &str, value: Value) -> Result<Value, String> {
+        if self.source[self.position..].starts_with(keyword) {
+            self.position += keyword.len();
+            Ok(value)
+        } else {
+            self.unexpected(keyword)
+        }
+    }
+
+    fn parse_array(&mut self, depth: usize) -> Result<Value, String> {
+        self.expect('[')?;
+        let mut items = VVec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.position += 1;
+            return Ok(self.vm.gc.store(items));
+        }
+
+        loop {
+            self.skip_whitespace();
+            items.push(self.parse_value(depth + 1)?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.position += 1,
+                Some(']') => {
+                    self.position += 1;
+                    return Ok(self.vm.gc.store(items));
+                }
+                _ => return self.unexpected("',' or ']'"),
+            }
+        }
+    }
+
+    fn parse_object(&mut self, depth: usize) -> Result<Value, String> {
+        self.expect('{')?;
+        let mut map = VMap::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.position += 1;
+            return Ok(self.vm.gc.store(map));
+        }
+
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some('"') {
+                return self.unexpected("string key");
+            }
+            let key = self.parse_string()?;
+            let key = self.vm.gc.new_string(&key);
+            self.skip_whitespace();
+            self.expect(':')?;
+            self.skip_whitespace();
+            let value = self.parse_value(depth + 1)?;
+            map.insert(key, value);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.position += 1,
+                Some('}') => {
+                    self.position += 1;
+                    return Ok(self.vm.gc.store(map));
+                }
+                _ => return self.unexpected("',' or '}'"),
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut result = String::new();
+        loop {
+            let start = self.position;
+            match self.bump() {
+                None => return self.error("unterminated string".to_string()),
+                Some('"') => return Ok(result),
+                Some('\\') => {
+                    let escaped = match self.bump() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => self.parse_unicode_escape(start)?,
+                        _ => {
+                            self.position = start;
+                            return self.error("invalid escape sequence".to_string());
+                        }
+                    };
+                    result.push(escaped);
+                }
+                Some(c) if (c as u32) < 0x20 => {
+                    self.position = start;
+                    return self.error("control character in string".to_string());
+                }
+                Some(c) => result.push(c),
+            }
+        }
+    }
+
+    /// parses hex digits of `\uXXXX` escape (possibly followed by low surrogate)
+    fn parse_unicode_escape(&mut self, escape_start: usize) -> Result<char, String> {
+        let high = self.parse_hex4(escape_start)?;
+        let code = if (0xD800..0xDC00).contains(&high) {
+            if !self.source[self.position..].starts_with("\\u") {
+                self.position = escape_start;
+                return self.error("unpaired surrogate in unicode escape".to_string());
+            }
+            self.position += 2;
+            let low = self.parse_hex4(escape_start)?;
+            if !(0xDC00..0xE000).contains(&low) {
+                self.position = escape_start;
+                return self.error("unpaired surrogate in unicode escape".to_string());
+            }
+            0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+        } else {
+            high
+        };
+
+        match char::from_u32(code) {
+            Some(c) => Ok(c),
+            None => {
+                self.position = escape_start;
+                self.error("invalid unicode escape".to_string())
+            }
+        }
+    }
+
+    fn parse_hex4(&mut self, escape_start: usize) -> Result<u32, String> {
+        //`from_str_radix` alone would accept sign, e.g. `\u+041`
+        let digits = self
+            .source
+            .get(self.position..self.position + 4)
+            .filter(|d| d.bytes().all(|b| b.is_ascii_hexdigit()));
+        match digits.and_then(|d| u32::from_str_radix(d, 16).ok()) {
+            Some(code) => {
+                self.position += 4;
+                Ok(code)
+            }
+            None => {
+                self.position = escape_start;
+                self.error("invalid unicode escape".to_string())
+            }
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<Value, String> {
+        let start = self.position;
+        let mut is_float = false;
+
+        if self.peek() == Some('-') {
+            self.position += 1;
+        }
+
+        match self.peek() {
+            Some('0') => self.position += 1,
+            Some('1'..='9') => self.skip_digits(),
+            _ => return self.unexpected("digit"),
+        }
+
+        if self.peek() == Some('.') {
+            is_float = true;
+            self.position += 1;
+            if !matches!(self.peek(), Some('0'..='9')) {
+                return self.unexpected("digit");
+            }
+            self.skip_digits();
+        }
+
+        if let Some('e' | 'E') = self.peek() {
+            is_float = true;
+            self.position += 1;
+            if let Some('+' | '-') = self.peek() {
+                self.position += 1;
+            }
+            if !matches!(self.peek(), Some('0'..='9')) {
+                return self.unexpected("digit");
+            }
+            self.skip_digits();
+        }
+
+        let literal = &self.source[start..self.position];
+        if !is_float {
+            if let Ok(n) = literal.parse::<i64>() {
+                return Ok(Value::Int(n));
+            }
+            //integers that do not fit into 64 bits become bignums
+            return Ok(self
+                .vm
+                .gc
+                .store(literal.parse::<num_bigint::BigInt>().unwrap()));
+        }
+
+        Ok(Value::Float(literal.parse::<f64>().unwrap()))
+    }
+
+    fn skip_digits(&mut self) {
+        while let Some('0'..='9') = self.peek() {
+            self.position += 1;
+        }
+    }
+}
+
+struct Writer {
+    output: String,
+    indent: Option<usize>,
+    /// heap objects that are currently being written, used to detect cycles
+    visiting: Vec<*const OwnedObjectItem>,
+    path: Vec<String>,
+}
+
+impl Writer {
+    fn error<T>(&self, message: String) -> Result<T, String> {
+        if self.path.is_empty() {
+            Err(message)
+        } else {
+            Err(format!("{} at `{}`", message, self.path.concat()))
+        }
+    }
+
+    fn newline(&mut self, depth: usize) {
+        if let Some(indent) = self.indent {
+            self.output.push('\n');
+            self.output.extend(std::iter::repeat_n(' ', indent * depth));
+        }
+    }
+
+    fn write_value(&mut self, value: &Value, depth: usize) -> Result<(), String> {
+        if depth > MAX_DEPTH {
+            return self.error("value is nested too deeply".to_string());
+        }
+
+        match value {
+            StackObject::Int(n) => write!(self.output, "{}", n).unwrap(),
+            &StackObject::Float(f) if f.is_finite() => write!(self.output, "{:?}", f).unwrap(),
+            StackObject::Float(f) => return self.error(format!("{} is not valid JSON number", f)),
+            &StackObject::Bool(b) => write!(self.output, "{}", b).unwrap(),
+            StackObject::Nothing => self.output.push_str("null"),
+            other => {
+                if let Some(s) = other.unwrap_any_str() {
+                    write_string(&mut self.output, s);
+                    return Ok(());
+                }
+
+                let item = match other.as_heap_object() {
+                    Some(item) => item,
+                    None => {
+                        return self
+                            .error(format!("{} is not JSON-serializable", other.type_string()))
+                    }
+                };
+
+                let ptr = item as *const OwnedObjectItem;
+                if self.visiting.contains(&ptr) {
+                    return self.error("cyclic structure cannot be serialized".to_string());
+                }
+                self.visiting.push(ptr);
+                self.write_heap_object(other, item, depth)?;
+                self.visiting.pop();
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_heap_object(
+        &mut self,
+        value: &Value,
+        item: &OwnedObjectItem,
+        depth: usize,
+    ) -> Result<(), String> {
+        match item {
+            OwnedObjectItem::Vector(items) => {
+                self.write_sequence('[', ']', depth, items.iter().enumerate(), |w, (i, v)| {
+                    w.path.push(format!("[{}]", i));
+                    w.write_value(v, depth + 1)
+                })
+            }
+
+            OwnedObjectItem::Map(map) => {
+                let mut entries = Vec::with_capacity(map.len());
+                for (key, value) in map.iter() {
+                    let key = match key {
+                        StackObject::Int(..) | StackObject::Float(..) | StackObject::Bool(..) => {
+                            key.to_string()
+                        }
+                        big if big.unwrap_bigint().is_some() => big.to_string(),
+                        other => match other.unwrap_any_str() {
+                            Some(s) => s.to_string(),
+                            None => {
+                                return self.error(format!(
+                                    "{} cannot be used as JSON object key",
+                                    other.type_string()
+                                ))
+                            }
+                        },
+                    };
+                    entries.push((key, value));
+                }
+                //maps are unordered, so keys are sorted to produce stable output
+                entries.sort_by(|(k1, _), (k2, _)| k1.cmp(k2));
+                self.write_object(entries.into_iter(), depth)
+            }
+
+            OwnedObjectItem::StructInstance(instance) => {
+                self.write_object(instance.fields.iter().map(|(k, v)| (k.clone(), v)), depth)
+            }
+
+            OwnedObjectItem::Box(boxed) => self.write_value(&boxed.0, depth),
+
+            OwnedObjectItem::BigInt(n) => {
+                write!(self.output, "{}", n).unwrap();
+                Ok(())
+            }
+
+            _ => self.error(format!("{} is not JSON-serializable", value.type_string())),
+        }
+    }
+
+    fn write_object<'a>(
+        &mut self,
+        entries: impl Iterator<Item = (String, &'a Value)>,
+        depth: usize,
+    ) -> Result<(), String> {
+        self.write_sequence('{', '}', depth, entries, |w, (k, v)| {
+            write_string(&mut w.output, &k);
+            w.output
+                .push_str(if w.indent.is_some() { ": " } else { ":" });
+            w.path.push(format!(".{}", k));
+            w.write_value(v, depth + 1)
+        })
+    }
+
+    fn write_sequence<T>(
+        &mut self,
+        open: char,
+        close: char,
+        depth: usize,
+        items: impl Iterator<Item = T>,
+        mut write_item: impl FnMut(&mut Self, T) -> Result<(), String>,
+    ) -> Result<(), String> {
+        self.output.push(open);
+        let mut is_empty = true;
+        for item in items {
+            if !is_empty {
+                self.output.push(',');
+            }
+            is_empty = false;
+            self.newline(depth + 1);
+            write_item(self, item)?;
+            self.path.pop();
+        }
+        if !is_empty {
+            self.newline(depth);
+        }
+        self.output.push(close);
+        Ok(())
+    }
+}
+
+fn write_string(output: &mut String, s: &str) {
+    output.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(output, "\\u{:04x}", c as u32).unwrap(),
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+}
+
+#[cfg(test)]
+mod test {
+    use serde::Deserialize;
+
+    use crate::execution::module::Module;
+    use crate::execution::vm::InterpretErrorKind;
+    use crate::interpreter::{Error, Handle, Interpreter};
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Payload {
+        name: String,
+        values: Vec<f64>,
+        nested: Option<Box<Payload>>,
+    }
+
+    fn setup(program: &str) -> (Interpreter, Module) {
+        let interpreter = Interpreter::new();
+        let module = Module::from_dot_notation("`TEST`");
+        let program = format!("import json.parse\nimport json.stringify\n{}", program);
+        interpreter.run_source(&program, &module).unwrap();
+        (interpreter, module)
+    }
+
+    fn call<'i>(
+        interpreter: &'i Interpreter,
+        module: &Module,
+        name: &str,
+        args: Vec<Handle>,
+    ) -> Result<Handle<'i>, String> {
+        let function = interpreter.get_global(module, name).unwrap();
+        interpreter.call(function, args).map_err(|e| match e {
+            Error::Runtime(InterpretErrorKind::NativeError { message }, _) => message,
+            other => panic!("unexpected error {:?}", other),
+        })
+    }
+
+    #[test]
+    fn parse_should_produce_values() {
+        let (interpreter, module) = setup("");
+        let source = interpreter
+            .to_value(
+                r#" {"name": "a\tb\u00e9\ud83d\ude00", "values": [1, -2.5e1, 0], "nested": null} "#,
+            )
+            .unwrap();
+        let value = call(&interpreter, &module, "parse", vec![source]).unwrap();
+
+        assert_eq!(
+            interpreter.from_value::<Payload>(&value).unwrap(),
+            Payload {
+                name: "a\tb\u{e9}\u{1f600}".to_string(),
+                values: vec![1.0, -25.0, 0.0],
+                nested: None,
+            }
+        );
+    }
+
+    #[test]
+    fn parse_should_report_error_position() {
+        let (interpreter, module) = setup("");
+        for (source, expected) in [
+            (
+                "{\n  \"a\": tru\n}",
+                "expected true, got 't' at line 2, column 8",
+            ),
+            (
+                "[1, 2",
+                "expected ',' or ']', got end of input at line 1, column 6",
+            ),
+            ("[01]", "expected ',' or ']', got '1' at line 1, column 3"),
+            ("\"\\x\"", "invalid escape sequence at line 1, column 2"),
+            ("\"\\u+041\"", "invalid unicode escape at line 1, column 2"),
+            ("\"\\u004\"", "invalid unicode escape at line 1, column 2"),
+            (
+                "{} {}",
+                "expected end of input, got '{' at line 1, column 4",
+            ),
+        ] {
+            let source = interpreter.to_value(source).unwrap();
+            let error = call(&interpreter, &module, "parse", vec![source]).unwrap_err();
+            assert_eq!(error, format!("json.parse: {}", expected));
+        }
+    }
+
+    #[test]
+    fn stringify_should_write_structs_and_detect_cycles() {
+        let (interpreter, module) = setup(
+            "
+struct Point:
+    x
+    y
+
+var point = Point(1, list(2.0, Nothing, true))
+var node = Point(1, 2)
+node.y = node
+",
+        );
+
+        let point = interpreter.get_global(&module, "point").unwrap();
+        let compact = call(&interpreter, &module, "stringify", vec![point.clone()]).unwrap();
+        assert_eq!(
+            interpreter.from_value::<String>(&compact).unwrap(),
+            r#"{"x":1,"y":[2.0,null,true]}"#
+        );
+
+        let pretty = call(
+            &interpreter,
+            &module,
+            "stringify",
+            vec![point, interpreter.to_value(&2).unwrap()],
+        )
+        .unwrap();
+        assert_eq!(
+            interpreter.from_value::<String>(&pretty).unwrap(),
+            "{\n  \"x\": 1,\n  \"y\": [\n    2.0,\n    null,\n    true\n  ]\n}"
+        );
+
+        let node = interpreter.get_global(&module, "node").unwrap();
+        let error = call(&interpreter, &module, "stringify", vec![node]).unwrap_err();
+        assert_eq!(
+            error,
+            "json.stringify: cyclic structure cannot be serialized at `.y`"
+        );
+    }
+}
@@ -0,0 +1,230 @@
+//! project manifest (`blop.toml`) describing package and its local dependencies:
+//!
+//! ```toml
+//! [package]
+//! name = "app"
+//! version = "0.1.0"
+//! source = "src"      # directory with modules, defaults to manifest directory
+//! entry = "main.txt"  # relative to source directory, used by `blop run`
+//!
+//! [dependencies]
+//! textutils = { path = "../textutils" }
+//! ```
+//!
+//! Dependency `textutils` makes `import textutils.words` load `words.txt` from the source
+//! directory of the dependency (its own manifest is honored, including its dependencies).
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use super::resolver::{ModuleResolver, STDLIB_PACKAGE};
+
+pub const MANIFEST_FILE: &str = "blop.toml";
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    pub package: Package,
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, Dependency>,
+    /// directory containing manifest, paths in manifest are relative to it
+    #[serde(skip)]
+    pub root: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Package {
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub source: PathBuf,
+    pub entry: Option<PathBuf>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Dependency {
+    pub path: PathBuf,
+}
+
+impl Manifest {
+    pub fn parse(text: &str, root: &Path) -> Result<Self, String> {
+        let mut manifest: Manifest = toml::from_str(text).map_err(|e| e.message().to_string())?;
+        manifest.root = root.to_path_buf();
+
+        for name in manifest.dependencies.keys() {
+            if !is_package_name(name) {
+                return Err(format!("invalid dependency name {:?}", name));
+            }
+            if name == STDLIB_PACKAGE {
+                return Err(format!("dependency name {} is reserved", name));
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// reads manifest stored in given directory
+    pub fn load(dir: &Path) -> Result<Self, String> {
+        let path = dir.join(MANIFEST_FILE);
+        let text =
+            std::fs::read_to_string(&path).map_err(|e| format!("{} ({})", e, path.display()))?;
+        Self::parse(&text, dir).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// finds directory with manifest, starting from `dir` and going up
+    pub fn find(dir: &Path) -> Option<PathBuf> {
+        dir.ancestors()
+            .find(|d| d.join(MANIFEST_FILE).is_file())
+            .map(Path::to_path_buf)
+    }
+
+    pub fn source_root(&self) -> PathBuf {
+        self.root.join(&self.package.source)
+    }
+
+    pub fn entry_point(&self) -> Result<PathBuf, String> {
+        self.package
+            .entry
+            .as_ref()
+            .map(|entry| self.source_root().join(entry))
+            .ok_or_else(|| format!("package {} does not declare entry point", self.package.name))
+    }
+
+    /// maps dependencies (and their own dependencies) to packages of resolver
+    pub fn configure(&self, resolver: &mut ModuleResolver) -> Result<(), String> {
+        let mut registered = BTreeMap::new();
+        self.register_dependencies(resolver, &mut registered)
+    }
+
+    fn register_dependencies(
+        &self,
+        resolver: &mut ModuleResolver,
+        registered: &mut BTreeMap<String, PathBuf>,
+    ) -> Result<(), String> {
+        for (name, dependency) in &self.dependencies {
+            let dir = self.root.join(&dependency.path);
+            let manifest = match dir.join(MANIFEST_FILE).is_file() {
+                true => Some(Self::load(&dir)?),
+                false => None,
+            };
+            let source_root = manifest
+                .as_ref()
+                .map(Manifest::source_root)
+                .unwrap_or_else(|| dir.clone());
+
+            match registered.get(name) {
+                Some(previous) if previous == &source_root => continue,
+                Some(previous) => {
+                    return Err(format!(
+                        "dependency {} is declared with different paths ({} and {})",
+                        name,
+                        previous.display(),
+                        source_root.display()
+                    ))
+                }
+                None => {}
+            }
+
+            registered.insert(name.clone(), source_root.clone());
+            resolver.add_package(name, source_root);
+            if let Some(manifest) = manifest {
+                manifest.register_dependencies(resolver, registered)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_package_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::Manifest;
+    use crate::execution::module::Module;
+    use crate::execution::resolver::{ModuleResolver, ModuleSource};
+
+    #[test]
+    fn manifest_should_be_parsed() {
+        let manifest = Manifest::parse(
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\nsource = \"src\"\nentry = \"main.txt\"\n\n[dependencies]\nutils = { path = \"../utils\" }\n",
+            Path::new("project"),
+        )
+        .unwrap();
+
+        assert_eq!(manifest.package.name, "app");
+        assert_eq!(manifest.package.version, "0.1.0");
+        assert_eq!(
+            manifest.entry_point(),
+            Ok(Path::new("project").join("src").join("main.txt"))
+        );
+        assert_eq!(
+            manifest.dependencies["utils"].path,
+            Path::new("../utils").to_path_buf()
+        );
+    }
+
+    #[test]
+    fn invalid_manifests_should_be_rejected() {
+        let cases = [
+            ("[package]\nname = \"app\"\n", "missing field `version`"),
+            (
+                "[package]\nname = \"a\"\nversion = \"1\"\nauthor = \"me\"\n",
+                "unknown field `author`",
+            ),
+            (
+                "[package]\nname = \"a\"\nversion = \"1\"\n[dependencies]\nstd = { path = \"x\" }\n",
+                "dependency name std is reserved",
+            ),
+            (
+                "[package]\nname = \"a\"\nversion = \"1\"\n[dependencies]\n\"my-lib\" = { path = \"x\" }\n",
+                "invalid dependency name \"my-lib\"",
+            ),
+        ];
+        for (text, expected) in cases {
+            let error = Manifest::parse(text, Path::new(".")).unwrap_err();
+            assert!(error.contains(expected), "{}", error);
+        }
+
+        let manifest =
+            Manifest::parse("[package]\nname = \"a\"\nversion = \"1\"\n", Path::new("."));
+        assert_eq!(
+            manifest.unwrap().entry_point(),
+            Err("package a does not declare entry point".to_string())
+        );
+    }
+
+    #[test]
+    fn dependencies_should_be_mapped_into_resolver() {
+        let manifest = Manifest::load(Path::new("examples/project")).unwrap();
+        let mut resolver = ModuleResolver::default();
+        manifest.configure(&mut resolver).unwrap();
+
+        //dependency of dependency is registered as well
+        let expected = [
+            (
+                "textutils.words",
+                "examples/project/vendor/textutils/src/words.txt",
+            ),
+            (
+                "casing.upper",
+                "examples/project/vendor/textutils/vendor/casing/upper.txt",
+            ),
+        ];
+        for (module, path) in expected {
+            assert_eq!(
+                resolver.resolve(&Module::from_dot_notation(module)),
+                Ok(ModuleSource::File(Path::new(path).to_path_buf()))
+            );
+        }
+    }
+}
@@ -0,0 +1,209 @@
+//! native `math` module: roots, powers, logarithms, trigonometry, rounding and integer helpers.
+//!
+//! Functions accept both `Int` and `Float` arguments. Rounding functions return `Int`,
+//! most of the others return `Float`
+use crate::data::objects::Value;
+use crate::data::value_ops::{integer_pow, normalize_bigint, to_bigint};
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::{FromPrimitive, Signed, ToPrimitive};
+
+use super::arity::Arity;
+use super::builtins::{compare_values, BuiltinError, NativeModule};
+use super::vm::VM;
+
+pub fn math_module() -> NativeModule {
+    let mut module = NativeModule::new("math");
+
+    module
+        .add_value("pi", Value::Float(std::f64::consts::PI))
+        .unwrap();
+    module
+        .add_value("e", Value::Float(std::f64::consts::E))
+        .unwrap();
+    module
+        .add_value("inf", Value::Float(f64::INFINITY))
+        .unwrap();
+    module.add_value("nan", Value::Float(f64::NAN)).unwrap();
+
+    macro_rules! float_function {
+        ($name:expr, $function:expr) => {
+            module.add_function($name, Arity::Exact(1), |args, _vm| {
+                let x = number_arg($name, &args[0])?;
+                Ok(Value::Float($function(x)))
+            })
+        };
+    }
+
+    float_function!("sqrt", f64::sqrt);
+    float_function!("exp", f64::exp);
+    float_function!("sin", f64::sin);
+    float_function!("cos", f64::cos);
+    float_function!("tan", f64::tan);
+    float_function!("asin", f64::asin);
+    float_function!("acos", f64::acos);
+    float_function!("atan", f64::atan);
+
+    module.add_function("atan2", Arity::Exact(2), |args, _vm| {
+        let y = number_arg("atan2", &args[0])?;
+        let x = number_arg("atan2", &args[1])?;
+        Ok(Value::Float(y.atan2(x)))
+    });
+
+    //log(x) is natural logarithm, log(x, base) uses given base
+    module.add_function("log", Arity::AtLeast(1), |args, _vm| {
+        let x = number_arg("log", &args[0])?;
+        match args[1].unwrap_vector().unwrap().as_slice() {
+            [] => Ok(Value::Float(x.ln())),
+            [base] => Ok(Value::Float(x.log(number_arg("log", base)?))),
+            rest => Err(format!("math.log expected at most 2 args, got {}", rest.len() + 1).into()),
+        }
+    });
+
+    //integer powers with non-negative exponent stay integers
+    module.add_function("pow", Arity::Exact(2), |args, vm| {
+        match (&args[0], &args[1]) {
+            (base, &Value::Int(exponent)) if base.is_integer() && exponent >= 0 => {
+                integer_pow(base, exponent as u64, vm.gc).ok_or_else(|| {
+                    BuiltinError::Other(format!("math.pow: exponent {} is too large", exponent))
+                })
+            }
+            (base, exponent) => Ok(Value::Float(
+                number_arg("pow", base)?.powf(number_arg("pow", exponent)?),
+            )),
+        }
+    });
+
+    macro_rules! rounding_function {
+        ($name:expr, $function:expr) => {
+            module.add_function($name, Arity::Exact(1), |args, vm| {
+                if args[0].is_integer() {
+                    return Ok(args[0].clone());
+                }
+                float_to_int(
+                    concat!("math.", $name),
+                    $function(number_arg($name, &args[0])?),
+                    vm,
+                )
+            })
+        };
+    }
+
+    rounding_function!("floor", f64::floor);
+    rounding_function!("ceil", f64::ceil);
+    //halfway cases are rounded away from zero
+    rounding_function!("round", f64::round);
+
+    module.add_function("min", Arity::AtLeast(1), |args, _vm| {
+        extremum("min", &args, std::cmp::Ordering::Less)
+    });
+
+    module.add_function("max", Arity::AtLeast(1), |args, _vm| {
+        extremum("max", &args, std::cmp::Ordering::Greater)
+    });
+
+    module.add_function("gcd", Arity::Exact(2), |args, vm| {
+        let a = int_arg("gcd", &args[0])?;
+        let b = int_arg("gcd", &args[1])?;
+        Ok(normalize_bigint(a.gcd(&b), vm.gc))
+    });
+
+    module.add_function("lcm", Arity::Exact(2), |args, vm| {
+        let a = int_arg("lcm", &args[0])?;
+        let b = int_arg("lcm", &args[1])?;
+        Ok(normalize_bigint(a.lcm(&b).abs(), vm.gc))
+    });
+
+    module
+}
+
+fn number_arg(function: &str, value: &Value) -> Result<f64, BuiltinError> {
+    match *value {
+        Value::Int(n) => Ok(n as f64),
+        Value::Float(f) => Ok(f),
+        ref big if big.is_integer() => {
+            Ok(big.unwrap_bigint().unwrap().to_f64().unwrap_or(f64::NAN))
+        }
+        _ => Err(format!(
+            "math.{} expected number, got {}",
+            function,
+            value.type_string()
+        )
+        .into()),
+    }
+}
+
+fn int_arg(function: &str, value: &Value) -> Result<BigInt, BuiltinError> {
+    to_bigint(value).ok_or_else(|| {
+        BuiltinError::Other(format!(
+            "math.{} expected Int, got {}",
+            function,
+            value.type_string()
+        ))
+    })
+}
+
+/// converts already rounded float into `Int` (promoting huge values to bignums), failing for
+/// nan and infinities
+pub fn float_to_int(function: &str, f: f64, vm: &mut VM) -> Result<Value, BuiltinError> {
+    match BigInt::from_f64(f) {
+        Some(n) if f.is_finite() => Ok(normalize_bigint(n, vm.gc)),
+        _ => Err(format!("{}: cannot convert {} to Int", function, f).into()),
+    }
+}
+
+fn extremum(
+    function: &str,
+    args: &[Value],
+    wanted: std::cmp::Ordering,
+) -> Result<Value, BuiltinError> {
+    let mut best = &args[0];
+    for candidate in args[1].unwrap_vector().unwrap().iter() {
+        for value in [best, candidate] {
+            if let Value::Float(f) = value {
+                if f.is_nan() {
+                    return Err(format!("math.{} cannot compare nan", function).into());
+                }
+            }
+        }
+        if compare_values(candidate, best)? == wanted {
+            best = candidate;
+        }
+    }
+    Ok(best.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test::assert_errors;
+
+    #[test]
+    fn math_functions_should_report_errors() {
+        let cases = [
+            ("sqrt(\"4\")", "math.sqrt expected number, got String"),
+            ("floor(inf)", "math.floor: cannot convert inf to Int"),
+            (
+                "pow(10, 10000000000)",
+                "math.pow: exponent 10000000000 is too large",
+            ),
+            ("pow(3, 524289)", "math.pow: exponent 524289 is too large"),
+            ("gcd(1.5, 2)", "math.gcd expected Int, got Float"),
+            ("max(1, \"a\")", "cannot compare String and Int"),
+            ("min(1, nan)", "math.min cannot compare nan"),
+            ("log(1, 2, 3)", "math.log expected at most 2 args, got 3"),
+        ];
+        let programs = cases.map(|(program, expected)| {
+            let program = format!(
+                "import math.{}\nimport math.inf\nimport math.nan\n{}",
+                &program[..program.find('(').unwrap()],
+                program
+            );
+            (program, expected)
+        });
+        assert_errors(
+            programs
+                .iter()
+                .map(|(program, expected)| (program.as_str(), *expected)),
+        );
+    }
+}
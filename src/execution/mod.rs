@@ -1,5 +1,10 @@
 pub mod arity;
 pub mod builtins;
 pub mod chunk;
+pub mod iteration;
+pub mod json;
+pub mod manifest;
+pub mod math;
 pub mod module;
+pub mod resolver;
 pub mod vm;
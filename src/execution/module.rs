@@ -1,5 +1,6 @@
 use std::{
     error::Error,
+    fmt::Display,
     path::{Component, Path, PathBuf},
 };
 
@@ -9,10 +10,35 @@ use crate::{
     parsing,
 };
 
+use super::chunk::{Chunk, Opcode};
+use super::resolver::ModuleSource;
 use super::vm::VM;
 
 pub const FILE_EXTENSION: &str = "txt";
 
+/// global that is true in the script run by interpreter and false in imported modules,
+/// so `if __main__` guards self-tests and demos of a module
+pub const MAIN_FLAG: &str = "__main__";
+
+/// function that the command line interpreter calls with script arguments after running script
+pub const MAIN_FUNCTION: &str = "main";
+
+/// checks whether code of script (or of its functions other than `main` itself) reads global
+/// `main`, which means that script calls it on its own
+pub fn refers_to_main(chunk: &Chunk) -> bool {
+    let reads_main = chunk.code.iter().any(|opcode| match opcode {
+        Opcode::LoadGlobal(idx) => chunk.global_names[*idx as usize] == MAIN_FUNCTION,
+        _ => false,
+    });
+    reads_main
+        || chunk
+            .constants
+            .iter()
+            .filter_map(Value::unwrap_function)
+            .filter(|function| function.name.get_string() != Some(MAIN_FUNCTION))
+            .any(|function| refers_to_main(function))
+}
+
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 pub struct Module(Vec<String>);
 
@@ -51,6 +77,12 @@ impl From<&Module> for PathBuf {
     }
 }
 
+impl Display for Module {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.join("."))
+    }
+}
+
 impl Module {
     pub fn new(items: Vec<String>) -> Self {
         Self(items)
@@ -59,6 +91,36 @@ impl Module {
     pub fn from_dot_notation(module_name: &str) -> Self {
         Self(module_name.split('.').map(ToOwned::to_owned).collect())
     }
+
+    /// module nested in this one, e.g. `a.b.c` for `a.b` and `c`
+    pub fn submodule(&self, name: &str) -> Self {
+        let mut items = self.0.clone();
+        items.push(name.to_string());
+        Self(items)
+    }
+
+    /// module `level` steps up, e.g. `a` for `a.b.c` and 2. None if it would leave
+    /// top-level package
+    pub fn ancestor(&self, level: usize) -> Option<Self> {
+        match self.0.len().checked_sub(level) {
+            Some(len) if len > 0 => Some(Self(self.0[..len].to_vec())),
+            _ => None,
+        }
+    }
+
+    pub fn parts(&self) -> &[String] {
+        &self.0
+    }
+
+    /// first component of module name, e.g. `std` for `std.loops`
+    pub fn package(&self) -> &str {
+        &self.0[0]
+    }
+}
+
+/// names starting with underscore are private, i.e. cannot be imported by other modules
+pub fn is_private_name(name: &str) -> bool {
+    name.starts_with('_')
 }
 
 fn normalize_string(s: String) -> String {
@@ -124,21 +186,52 @@ pub fn compile_program(
     Ok(pointer)
 }
 
+/// compiles script file. Its directory becomes the first entry of module search path (see
+/// [`ModuleResolver`](super::resolver::ModuleResolver))
 pub fn compile_file(file_path: &Path, vm: &mut VM) -> Result<(String, Value), Box<dyn Error>> {
-    let program = match std::fs::read_to_string(file_path) {
-        Ok(content) => content,
-        Err(e) => {
-            return Err(format!("{e} ({})", file_path.display()).into());
-        }
-    };
+    let module = script_module(file_path)?;
 
-    let module = Module::try_from(file_path)
-        .map_err(|_| format!("failed to build module from path {file_path:?}"))?;
+    match file_path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => vm.resolver.set_script_dir(dir),
+        _ => vm.resolver.set_script_dir("."),
+    }
 
+    let program = read_source(file_path)?;
     let pointer = compile_program(program.clone(), &module, vm)?;
+    vm.set_global(&module, MAIN_FLAG, true.into());
     Ok((program, pointer))
 }
 
+/// module of script run directly, e.g. `` `main` `` for `src/main.txt`
+pub fn script_module(file_path: &Path) -> Result<Module, Box<dyn Error>> {
+    //quoted name never clashes with importable modules, e.g. script `math.txt` and native `math`
+    file_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .map(|s| Module::new(vec![format!("`{}`", s)]))
+        .ok_or_else(|| format!("failed to build module from path {file_path:?}").into())
+}
+
+/// compiles already resolved module, returning its source (used for error reporting) and
+/// entry point
+pub fn compile_module_source(
+    source: &ModuleSource,
+    module: &Module,
+    vm: &mut VM,
+) -> Result<(String, Value), Box<dyn Error>> {
+    let program = match source {
+        ModuleSource::File(path) => read_source(path)?,
+        ModuleSource::Embedded(program) => program.to_string(),
+    };
+
+    let pointer = compile_program(program.clone(), module, vm)?;
+    Ok((program, pointer))
+}
+
+fn read_source(file_path: &Path) -> Result<String, Box<dyn Error>> {
+    std::fs::read_to_string(file_path).map_err(|e| format!("{e} ({})", file_path.display()).into())
+}
+
 #[allow(dead_code)]
 pub fn run_file(filename: &Path) -> Result<(), Box<dyn Error>> {
     let mut gc = unsafe { GC::default_gc() };
@@ -156,7 +249,7 @@ pub fn exec_with_error_printing(
     source_code: &str,
 ) -> Result<Value, Box<dyn Error>> {
     vm.run(pointer)
-        .map_err(|e| crate::display_error(source_code, e).into())
+        .map_err(|e| crate::interpreter::display_error(source_code, e).into())
 }
 
 #[cfg(test)]
@@ -198,4 +291,13 @@ mod test {
 
         assert_eq!(path, expected);
     }
+
+    #[test]
+    fn ancestor_should_stay_within_package() {
+        let module = Module::from_dot_notation("a.b.c");
+        assert_eq!(module.ancestor(1), Some(Module::from_dot_notation("a.b")));
+        assert_eq!(module.ancestor(2), Some(Module::from_dot_notation("a")));
+        assert_eq!(module.ancestor(3), None);
+        assert_eq!(module.ancestor(4), None);
+    }
 }
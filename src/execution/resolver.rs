@@ -0,0 +1,280 @@
+//! lookup of module source files on an ordered search path.
+//!
+//! Modules of packages declared as dependencies (see [`Manifest`](super::manifest::Manifest))
+//! are looked up only in directory of that package. `std.*` modules always come from standard
+//! library, embedded into the binary at build time unless `BLOP_STDLIB_DIR` points to on-disk
+//! copy. Other `import a.b.c` is looked up as `a/b/c.txt` in (first match wins):
+//! 1. directory of running script (working directory if source does not come from file)
+//! 2. directories listed in `BLOP_PATH` environment variable
+//! 3. include directories (`-I` flags of interpreter)
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+use super::module::Module;
+
+/// environment variable with additional module directories, separated like `PATH`
+pub const PATH_VARIABLE: &str = "BLOP_PATH";
+
+/// environment variable with directory containing `std` package. When set, stdlib modules are
+/// read from there instead of embedded copies, which is handy when working on stdlib itself
+pub const STDLIB_DIR_VARIABLE: &str = "BLOP_STDLIB_DIR";
+
+/// name of the top-level package of bundled standard library
+pub const STDLIB_PACKAGE: &str = "std";
+
+/// `(path, source)` of stdlib modules, paths look like `std/loops.txt` (see `build.rs`)
+static EMBEDDED_STDLIB: &[(&str, &str)] = include!(concat!(env!("OUT_DIR"), "/stdlib.rs"));
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ModuleSource {
+    File(PathBuf),
+    /// source of stdlib module bundled into the binary
+    Embedded(&'static str),
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ModuleResolver {
+    script_dir: Option<PathBuf>,
+    env_dirs: Vec<PathBuf>,
+    include_dirs: Vec<PathBuf>,
+    stdlib_dir: Option<PathBuf>,
+    packages: HashMap<String, PathBuf>,
+}
+
+impl ModuleResolver {
+    /// resolver using `BLOP_PATH` and `BLOP_STDLIB_DIR` of current process
+    pub fn from_env() -> Self {
+        let mut resolver = Self::new(std::env::var_os(PATH_VARIABLE).as_deref());
+        if let Some(dir) = std::env::var_os(STDLIB_DIR_VARIABLE).filter(|d| !d.is_empty()) {
+            resolver.set_stdlib_dir(dir);
+        }
+        resolver
+    }
+
+    /// `blop_path` is the value of `BLOP_PATH`, empty entries are ignored
+    pub fn new(blop_path: Option<&OsStr>) -> Self {
+        ModuleResolver {
+            env_dirs: blop_path
+                .map(|paths| {
+                    std::env::split_paths(paths)
+                        .filter(|p| !p.as_os_str().is_empty())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+
+    pub fn set_script_dir(&mut self, dir: impl Into<PathBuf>) {
+        self.script_dir = Some(dir.into());
+    }
+
+    pub fn add_include_dir(&mut self, dir: impl Into<PathBuf>) {
+        self.include_dirs.push(dir.into());
+    }
+
+    /// loads stdlib from given directory (containing `std` package) instead of embedded copies
+    pub fn set_stdlib_dir(&mut self, dir: impl Into<PathBuf>) {
+        self.stdlib_dir = Some(dir.into());
+    }
+
+    /// maps top-level package to directory, so `import name.a.b` loads `dir/a/b.txt`
+    pub fn add_package(&mut self, name: &str, dir: impl Into<PathBuf>) {
+        self.packages.insert(name.to_string(), dir.into());
+    }
+
+    /// embedded source of stdlib module stored under given path, e.g. `std/loops.txt`
+    pub fn embedded_module(path: &str) -> Option<&'static str> {
+        EMBEDDED_STDLIB
+            .iter()
+            .find(|(module_path, _)| *module_path == path)
+            .map(|(_, source)| *source)
+    }
+
+    /// finds source of module, error lists every location that was tried
+    pub fn resolve(&self, module: &Module) -> Result<ModuleSource, String> {
+        let relative: PathBuf = module.into();
+        let mut tried = vec![];
+
+        match self.packages.get(module.package()) {
+            Some(dir) if relative.components().count() > 1 => {
+                let path = dir.join(relative.components().skip(1).collect::<PathBuf>());
+                if path.is_file() {
+                    return Ok(ModuleSource::File(path));
+                }
+                return Err(format!(
+                    "module {} not found in package {}, tried:\n    {}",
+                    module,
+                    module.package(),
+                    path.display()
+                ));
+            }
+            _ => {}
+        }
+
+        //stray `std` directory next to script should not shadow bundled stdlib
+        if module.package() == STDLIB_PACKAGE {
+            match self.stdlib_dir.as_deref() {
+                Some(dir) => {
+                    let path = dir.join(&relative);
+                    if path.is_file() {
+                        return Ok(ModuleSource::File(path));
+                    }
+                    tried.push(path.display().to_string());
+                }
+                None => {
+                    let embedded_path = relative
+                        .iter()
+                        .map(|c| c.to_string_lossy())
+                        .collect::<Vec<_>>()
+                        .join("/");
+                    if let Some(source) = Self::embedded_module(&embedded_path) {
+                        return Ok(ModuleSource::Embedded(source));
+                    }
+                    tried.push(format!("<embedded stdlib>/{}", embedded_path));
+                }
+            }
+        } else {
+            let mut roots = vec![self.script_dir.as_deref().unwrap_or(Path::new("."))];
+            roots.extend(self.env_dirs.iter().map(PathBuf::as_path));
+            roots.extend(self.include_dirs.iter().map(PathBuf::as_path));
+
+            for root in roots {
+                let path = root.join(&relative);
+                if path.is_file() {
+                    return Ok(ModuleSource::File(path));
+                }
+                tried.push(path.display().to_string());
+            }
+        }
+
+        Err(format!(
+            "module {} not found, tried:{}",
+            module,
+            tried
+                .iter()
+                .map(|p| format!("\n    {}", p))
+                .collect::<String>()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsString;
+    use std::path::Path;
+
+    use super::{ModuleResolver, ModuleSource};
+    use crate::execution::module::Module;
+
+    #[test]
+    fn resolver_should_follow_search_order() {
+        let blop_path = std::env::join_paths(["missing", "examples"]).unwrap();
+        let mut resolver = ModuleResolver::new(Some(&blop_path));
+        resolver.set_script_dir("std");
+        resolver.add_include_dir("src");
+
+        //script directory comes first
+        assert_eq!(
+            resolver.resolve(&Module::from_dot_notation("loops")),
+            Ok(ModuleSource::File(Path::new("std").join("loops.txt")))
+        );
+        assert_eq!(
+            resolver.resolve(&Module::from_dot_notation("imports")),
+            Ok(ModuleSource::File(
+                Path::new("examples").join("imports.txt")
+            ))
+        );
+        assert!(matches!(
+            resolver.resolve(&Module::from_dot_notation("std.option")),
+            Ok(ModuleSource::Embedded(source)) if source.contains("enum Option")
+        ));
+
+        //on-disk stdlib replaces embedded one
+        resolver.set_stdlib_dir(".");
+        assert_eq!(
+            resolver.resolve(&Module::from_dot_notation("std.option")),
+            Ok(ModuleSource::File(
+                Path::new(".").join("std").join("option.txt")
+            ))
+        );
+    }
+
+    #[test]
+    fn resolver_should_list_tried_locations() {
+        let blop_path = OsString::from("");
+        let mut resolver = ModuleResolver::new(Some(&blop_path));
+        resolver.add_include_dir("lib");
+
+        let message = resolver
+            .resolve(&Module::from_dot_notation("app.missing"))
+            .unwrap_err();
+
+        let expected_locations = [
+            Path::new(".").join("app").join("missing.txt"),
+            Path::new("lib").join("app").join("missing.txt"),
+        ];
+        assert!(message.starts_with("module app.missing not found, tried:"));
+        for location in expected_locations {
+            assert!(
+                message.contains(&format!("\n    {}", location.display())),
+                "{}",
+                message
+            );
+        }
+
+        let message = resolver
+            .resolve(&Module::from_dot_notation("std.missing"))
+            .unwrap_err();
+        assert_eq!(
+            message,
+            "module std.missing not found, tried:\n    <embedded stdlib>/std/missing.txt"
+        );
+    }
+
+    #[test]
+    fn local_std_directory_should_not_shadow_stdlib() {
+        let dir = std::env::temp_dir().join(format!("blop_stdlib_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("std")).unwrap();
+        std::fs::write(dir.join("std").join("option.txt"), "var shadowed = true\n").unwrap();
+
+        let blop_path = dir.clone().into_os_string();
+        let mut resolver = ModuleResolver::new(Some(&blop_path));
+        resolver.set_script_dir(&dir);
+        resolver.add_include_dir(&dir);
+        let option = Module::from_dot_notation("std.option");
+        let resolved = resolver.resolve(&option);
+
+        //explicit override is the only way to load stdlib from disk
+        resolver.set_stdlib_dir(&dir);
+        let overridden = resolver.resolve(&option);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(matches!(
+            resolved,
+            Ok(ModuleSource::Embedded(source)) if source.contains("enum Option")
+        ));
+        assert_eq!(
+            overridden,
+            Ok(ModuleSource::File(dir.join("std").join("option.txt")))
+        );
+    }
+
+    #[test]
+    fn whole_std_directory_should_be_embedded() {
+        for entry in std::fs::read_dir("std").unwrap() {
+            let path = entry.unwrap().path();
+            if !path.is_file() {
+                continue;
+            }
+            let embedded_path = format!("std/{}", path.file_name().unwrap().to_str().unwrap());
+            assert_eq!(
+                ModuleResolver::embedded_module(&embedded_path),
+                Some(std::fs::read_to_string(&path).unwrap().as_str()),
+                "{}",
+                embedded_path
+            );
+        }
+    }
+}
@@ -1,25 +1,51 @@
+use crate::data::conversion::{self, ConversionError};
 use crate::data::gc::GC;
-use crate::data::objects::{Closure, StackObject, VVec, Value, ValueBox};
-use crate::data::value_ops::{self, cast_binary, numeric_cast, NumberCastResult};
-use crate::execution::chunk::Opcode;
+use crate::data::objects::{Closure, NativeIterator, StackObject, VVec, Value, ValueBox};
+use crate::data::value_ops::{
+    self, cast_binary, integer_binary, integer_pow, numeric_cast, shift_fits, IntegerOp,
+};
+use crate::execution::chunk::{Chunk, Opcode};
+use crate::parsing::lexer::{Index, Token, TokenKind};
+use num_traits::{Signed, ToPrimitive};
 use std::cmp::Ordering;
 use std::collections::HashMap;
 
 use super::arity::Arity;
-use super::builtins::BuiltinMap;
-use super::module::Module;
+use super::builtins::{BuiltinError, BuiltinMap};
+use super::module::{is_private_name, Module, MAIN_FLAG};
+use super::resolver::ModuleResolver;
 
 const DEFAULT_MAX_STACK_SIZE: usize = 4 * 1024 * 1024 / std::mem::size_of::<StackObject>();
 //4MB
 
+/// how deeply `call`s and generators may nest inside each other. Every level takes native stack
+/// of host (up to 70KB in debug builds), so recursion through builtins like
+/// `def f(x) = list(1).map(f)` is limited separately from VM stacks
+const MAX_NATIVE_DEPTH: usize = 100;
+
 pub struct VM<'gc, 'builtins> {
     pub(super) stack: Vec<Value>,
     pub(super) call_stack: Vec<CallStackValue>,
     pub(super) loaded_modules: HashMap<Module, HashMap<String, Value>>,
+    /// modules whose top-level code is being executed, in import order
+    loading_modules: Vec<Module>,
+    /// previous globals of modules being reloaded, kept alive until reload finishes
+    replaced_globals: Vec<HashMap<String, Value>>,
     locals_offset: usize,
+    /// size of call stack at the moment innermost `run` was entered
+    frame_base: usize,
+    /// number of nested `call`s and generator resumptions
+    native_depth: usize,
+    /// tiny chunks of form `Call(n); Return` used by `call`, indexed by argument count
+    trampolines: HashMap<u16, Value>,
     stack_max_size: usize,
-    pub gc: &'gc mut GC,
+    /// objects keep raw pointer to their GC, so it is never handed out to code outside the crate
+    pub(crate) gc: &'gc mut GC,
     pub(crate) builtins: &'builtins BuiltinMap,
+    /// locates source files of imported modules
+    pub resolver: ModuleResolver,
+    /// command line arguments of script, returned by `args()`
+    pub script_args: Vec<String>,
 }
 
 pub struct CallStackValue {
@@ -31,6 +57,36 @@ pub struct CallStackValue {
 
 type Result<T> = std::result::Result<T, InterpretError>;
 
+/// why module could not be loaded
+#[derive(Debug)]
+pub enum ModuleError {
+    /// module was not found or failed to compile, or import is circular
+    Load(String),
+    /// top-level code of module raised error, described using source of module
+    Runtime(InterpretError, String),
+}
+
+impl std::fmt::Display for ModuleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModuleError::Load(message) => write!(f, "{}", message),
+            ModuleError::Runtime(_, message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl InterpretErrorKind {
+    /// innermost error, e.g. failed assert inside module whose import failed
+    pub fn root_cause(&self) -> &InterpretErrorKind {
+        match self {
+            InterpretErrorKind::ImportError {
+                cause: Some(cause), ..
+            } => cause.root_cause(),
+            other => other,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub struct InterpretError {
@@ -48,13 +104,33 @@ pub enum InterpretErrorKind {
     JumpBounds,
     AssertionFailure,
     StackOverflow,
-    TypeError { message: String },
+    TypeError {
+        message: String,
+    },
     MissedReturn,
-    NameError { name: String },
-    NativeError { message: String },
-    AttributeError { object: Value, missed_field: String },
-    IndexAttributeError { object: Value, missed_idx: usize },
-    ImportError { message: String },
+    NameError {
+        name: String,
+    },
+    NativeError {
+        message: String,
+    },
+    AttributeError {
+        object_type: &'static str,
+        missed_field: String,
+    },
+    IndexAttributeError {
+        object_type: &'static str,
+        missed_idx: usize,
+    },
+    /// module could not be loaded, `cause` is kind of error raised by its top-level code
+    ImportError {
+        message: String,
+        cause: Option<Box<InterpretErrorKind>>,
+    },
+    /// script called `exit(code)`, not an error as such but it unwinds the whole VM
+    Exit {
+        code: i32,
+    },
 }
 
 enum InstructionExecution {
@@ -66,6 +142,18 @@ enum InstructionExecution {
         new_ip: usize,
     },
     Termination,
+    Suspension,
+}
+
+/// how frame entered by `execute` was left
+enum Completion {
+    Returned(Value),
+    /// generator frame was suspended by `yield`, `frame` is its stack slice
+    Suspended {
+        value: Value,
+        frame: VVec,
+        ip: usize,
+    },
 }
 
 impl<'gc, 'builtins> VM<'gc, 'builtins> {
@@ -74,10 +162,17 @@ impl<'gc, 'builtins> VM<'gc, 'builtins> {
             stack: Vec::new(),
             call_stack: Vec::new(),
             loaded_modules: Default::default(),
+            loading_modules: vec![],
+            replaced_globals: vec![],
             locals_offset: 0,
+            frame_base: 0,
+            native_depth: 0,
+            trampolines: Default::default(),
             gc,
             stack_max_size: DEFAULT_MAX_STACK_SIZE,
             builtins,
+            resolver: ModuleResolver::from_env(),
+            script_args: vec![],
         }
     }
 
@@ -88,12 +183,6 @@ impl<'gc, 'builtins> VM<'gc, 'builtins> {
         old_stack_size
     }
 
-    pub fn reset_stacks(&mut self) {
-        self.call_stack.clear();
-        self.stack.clear();
-        self.locals_offset = 0;
-    }
-
     fn save_stacks(&self) -> (usize, usize, usize) {
         (self.call_stack.len(), self.stack.len(), self.locals_offset)
     }
@@ -105,19 +194,268 @@ impl<'gc, 'builtins> VM<'gc, 'builtins> {
     }
 
     pub fn maybe_create_module(&mut self, module: &Module) {
-        self.loaded_modules.entry(module.clone()).or_default();
+        self.module_globals(module);
+    }
+
+    /// globals of module, creating it if needed. Every module defines `__main__`, so that it
+    /// may be checked outside of entry module as well
+    fn module_globals(&mut self, module: &Module) -> &mut HashMap<String, Value> {
+        self.loaded_modules
+            .entry(module.clone())
+            .or_insert_with(|| HashMap::from([(MAIN_FLAG.to_string(), false.into())]))
     }
 
+    /// checks if module is loaded, native or can be found on search path
+    fn module_exists(&self, module: &Module) -> bool {
+        self.loaded_modules.contains_key(module)
+            || self.builtins.get_module(module).is_some()
+            || self.resolver.resolve(module).is_ok()
+    }
+
+    /// loads module unless it is already loaded. Source modules are executed on top of current
+    /// stacks which are restored afterwards. Importing module that is still loading is an error
+    /// naming the whole import cycle
+    fn load_module(&mut self, module: &Module) -> std::result::Result<(), ModuleError> {
+        if let Some(start) = self.loading_modules.iter().position(|m| m == module) {
+            let cycle = self.loading_modules[start..]
+                .iter()
+                .chain([module])
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            return Err(ModuleError::Load(format!(
+                "circular import: {}",
+                cycle.join(" -> ")
+            )));
+        }
+
+        if self.loaded_modules.contains_key(module) {
+            return Ok(());
+        }
+
+        if let Some(native_module) = self.builtins.get_module(module) {
+            self.loaded_modules
+                .insert(module.clone(), native_module.clone());
+            return Ok(());
+        }
+
+        use crate::execution::module::{self};
+        self.loading_modules.push(module.clone());
+        let result = self
+            .resolver
+            .resolve(module)
+            .map_err(Into::into)
+            .and_then(|source| module::compile_module_source(&source, module, self))
+            .map_err(|e| ModuleError::Load(e.to_string()))
+            .and_then(|(src, ptr)| {
+                self.run(ptr).map(|_| ()).map_err(|e| {
+                    let message = crate::interpreter::display_error(&src, e.clone());
+                    ModuleError::Runtime(e, message)
+                })
+            });
+        self.loading_modules.pop();
+
+        //partially initialized module should not be visible to later imports
+        if result.is_err() {
+            self.loaded_modules.remove(module);
+        }
+        result
+    }
+
+    /// recompiles module from source and replaces its globals, old globals stay in place if new
+    /// version fails to load. Values imported by name into other modules are not updated.
+    ///
+    /// With `rebind_methods`, descriptors of structs and enums whose fields (and variants) did not
+    /// change receive methods of new version and replace new descriptors, so live instances pick
+    /// up new `impl` code and share their type with new ones. Descriptors whose fields changed
+    /// keep their methods, which would otherwise read fields old instances do not have
+    pub fn reload_module(
+        &mut self,
+        module: &Module,
+        rebind_methods: bool,
+    ) -> std::result::Result<(), ModuleError> {
+        if self.builtins.get_module(module).is_some() {
+            return Err(ModuleError::Load(format!(
+                "native module {} cannot be reloaded",
+                module
+            )));
+        }
+        if self.loading_modules.contains(module) {
+            return Err(ModuleError::Load(format!(
+                "module {} cannot be reloaded while loading",
+                module
+            )));
+        }
+
+        let old_globals = match self.loaded_modules.remove(module) {
+            Some(globals) => globals,
+            None => return self.load_module(module),
+        };
+        self.replaced_globals.push(old_globals);
+        let result = self.load_module(module);
+        let old_globals = self.replaced_globals.pop().unwrap();
+
+        if let Err(e) = result {
+            self.loaded_modules.insert(module.clone(), old_globals);
+            return Err(e);
+        }
+
+        if rebind_methods {
+            for (name, new_value) in self.loaded_modules.get_mut(module).unwrap() {
+                if let Some(old_value) = old_globals.get(name) {
+                    if rebind_descriptor(old_value, new_value) {
+                        *new_value = old_value.clone();
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn import_module_object(&mut self, module: &Module) -> std::result::Result<Value, ModuleError> {
+        self.load_module(module)?;
+        Ok(self.gc.store(module.clone()))
+    }
+
+    /// looks up global variable `name` defined by (already loaded) module
+    pub fn get_global(&self, module: &Module, name: &str) -> Option<Value> {
+        self.loaded_modules.get(module)?.get(name).cloned()
+    }
+
+    /// allocates string, short ones are stored inline
+    pub fn new_string(&mut self, s: &str) -> Value {
+        self.gc.new_string(s)
+    }
+
+    /// converts Rust value into BLOP value. Used by native functions, so it is named like
+    /// [`Interpreter::to_value`](crate::Interpreter::to_value)
+    #[allow(clippy::wrong_self_convention)]
+    pub fn to_value<T: serde::Serialize + ?Sized>(
+        &mut self,
+        value: &T,
+    ) -> std::result::Result<Value, ConversionError> {
+        conversion::to_value(value, self.gc)
+    }
+
+    /// defines or overwrites global variable `name` of module
+    pub fn set_global(&mut self, module: &Module, name: &str, value: Value) {
+        self.module_globals(module).insert(name.to_string(), value);
+    }
+
+    /// calls any callable value (function, closure, partial, builtin, builtin method or struct
+    /// descriptor) with provided arguments and returns call result.
+    ///
+    /// Calls are re-entrant, so this may also be used by builtins to call back into user code.
+    /// Values held by builtins or by host stay alive during garbage collection
+    pub fn call(&mut self, callable: Value, args: Vec<Value>) -> Result<Value> {
+        let arity = match u16::try_from(args.len()) {
+            Ok(arity) => arity,
+            Err(_) => {
+                return Err(InterpretError {
+                    opcode_index: 0,
+                    chunk: self.get_trampoline(0),
+                    kind: InterpretErrorKind::TypeError {
+                        message: format!("too many arguments in call: {}", args.len()),
+                    },
+                })
+            }
+        };
+        let trampoline = self.get_trampoline(arity);
+        if self.native_depth >= MAX_NATIVE_DEPTH {
+            return Err(InterpretError {
+                opcode_index: 0,
+                chunk: trampoline,
+                kind: InterpretErrorKind::StackOverflow,
+            });
+        }
+
+        let state = self.save_stacks();
+        self.stack.push(callable);
+        self.stack.extend(args);
+        self.native_depth += 1;
+        let result = self.run(trampoline);
+        self.native_depth -= 1;
+        self.load_stacks(state);
+        result
+    }
+
+    fn get_trampoline(&mut self, arity: u16) -> Value {
+        if let Some(trampoline) = self.trampolines.get(&arity) {
+            return trampoline.clone();
+        }
+
+        let name = Token {
+            position: Index(0, 0),
+            kind: TokenKind::Name("`call`".to_string()),
+        };
+        let mut chunk = Chunk::new(name, Module::from_dot_notation("`native`"), Arity::Exact(0));
+        chunk.code = vec![Opcode::Call(arity), Opcode::Return];
+        chunk.opcode_to_line = vec![0, 0];
+
+        let trampoline = self.gc.store(chunk);
+        self.trampolines.insert(arity, trampoline.clone());
+        trampoline
+    }
+
+    /// runs chunk on top of current stacks. Stacks are restored after execution, so `run` may
+    /// be entered recursively (e.g. when importing modules or calling functions from builtins)
     pub fn run(&mut self, entry_point: StackObject) -> Result<StackObject> {
+        let state = self.save_stacks();
+        let frame_base = std::mem::replace(&mut self.frame_base, self.call_stack.len());
+        self.locals_offset = self.stack.len();
+
+        let result = self.execute(entry_point, 0);
+
+        self.frame_base = frame_base;
+        self.load_stacks(state);
+        result.map(|completion| match completion {
+            Completion::Returned(value) => value,
+            //only generator frames are suspended and they are entered by `resume_generator`
+            Completion::Suspended { .. } => unreachable!(),
+        })
+    }
+
+    /// runs suspended generator frame from `ip` until next `yield`. Returns yielded value with
+    /// updated frame, or `None` once generator function returns
+    pub(super) fn resume_generator(
+        &mut self,
+        function: Value,
+        frame: VVec,
+        ip: usize,
+    ) -> Result<Option<(Value, VVec, usize)>> {
+        if self.native_depth >= MAX_NATIVE_DEPTH {
+            return Err(InterpretError {
+                opcode_index: ip,
+                chunk: function,
+                kind: InterpretErrorKind::StackOverflow,
+            });
+        }
+
+        let state = self.save_stacks();
+        let frame_base = std::mem::replace(&mut self.frame_base, self.call_stack.len());
+        self.locals_offset = self.stack.len();
+        self.stack.extend(frame);
+
+        self.native_depth += 1;
+        let result = self.execute(function, ip);
+        self.native_depth -= 1;
+
+        self.frame_base = frame_base;
+        self.load_stacks(state);
+        result.map(|completion| match completion {
+            Completion::Returned(_) => None,
+            Completion::Suspended { value, frame, ip } => Some((value, frame, ip)),
+        })
+    }
+
+    fn execute(&mut self, entry_point: StackObject, start_ip: usize) -> Result<Completion> {
         use InterpretErrorKind::*;
-        self.reset_stacks();
-        let mut ip = 0;
+        let mut ip = start_ip;
         let mut current_chunk = entry_point;
 
         self.call_stack.push(CallStackValue {
             return_chunk: current_chunk.clone(),
             return_ip: 0,
-            return_locals_offset: 0,
+            return_locals_offset: self.locals_offset,
             return_stack_size: usize::MAX,
         });
 
@@ -186,7 +524,17 @@ impl<'gc, 'builtins> VM<'gc, 'builtins> {
                 InstructionExecution::Termination => {
                     let value = checked_stack_pop!()?;
                     //return immediately, without possibly triggering gc
-                    return Ok(value);
+                    return Ok(Completion::Returned(value));
+                }
+
+                InstructionExecution::Suspension => {
+                    let value = checked_stack_pop!()?;
+                    let frame = self.stack.split_off(self.locals_offset);
+                    return Ok(Completion::Suspended {
+                        value,
+                        frame,
+                        ip: ip + 1,
+                    });
                 }
             }
 
@@ -200,8 +548,10 @@ impl<'gc, 'builtins> VM<'gc, 'builtins> {
                     self.gc.mark_and_sweep(
                         self.stack
                             .iter()
-                            .chain(self.loaded_modules.values().flat_map(|v| v.values())),
-                        &*self.call_stack,
+                            .chain(self.loaded_modules.values().flat_map(|v| v.values()))
+                            .chain(self.replaced_globals.iter().flat_map(|v| v.values()))
+                            .chain(self.trampolines.values()),
+                        &self.call_stack,
                     );
                 }
             }
@@ -246,22 +596,75 @@ impl<'gc, 'builtins> VM<'gc, 'builtins> {
             };
         }
 
-        macro_rules! as_int {
-            ($value:expr) => {
-                Ok($value).and_then(|u| {
-                    u.clone().unwrap_int().ok_or_else(|| {
-                        runtime_error!(TypeError {
-                            message: format!(
-                                "expected {} but got {}",
-                                Value::Int(0).type_string(),
-                                u.type_string()
-                            )
-                        })
+        //integers promote to bignums on overflow, other numbers are handled by `cast_binary`
+        macro_rules! arithmetic_operator {
+            ($first:expr, $op:tt, $integer_op:expr, $second:expr) => {
+                match integer_binary(&$first, &$second, $integer_op, self.gc) {
+                    Some(value) => Some(value),
+                    None => cast_binary!(&$first, $op, &$second),
+                }
+            };
+        }
+
+        //operators defined only for integers
+        macro_rules! integer_operator {
+            ($integer_op:expr, $name:expr) => {{
+                let second_operand = checked_stack_pop!()?;
+                let first_operand = checked_stack_pop!()?;
+                let value = integer_binary(&first_operand, &second_operand, $integer_op, self.gc)
+                    .ok_or_else(|| {
+                    runtime_error!(InterpretErrorKind::TypeError {
+                        message: format!(
+                            "uncompatible types in {} (got {} and {})",
+                            $name,
+                            first_operand.type_string(),
+                            second_operand.type_string()
+                        )
                     })
+                })?;
+                self.stack.push(value);
+                InstructionExecution::NextInstruction
+            }};
+        }
+
+        macro_rules! builtin_result {
+            ($result:expr) => {
+                $result.map_err(|e| match e {
+                    //error happened inside user code called by builtin
+                    BuiltinError::Interpret(e) => e,
+                    BuiltinError::Exit(code) => runtime_error!(InterpretErrorKind::Exit { code }),
+                    other => runtime_error!(InterpretErrorKind::NativeError {
+                        message: other.to_string()
+                    }),
                 })
             };
         }
 
+        //`exit` in imported module stops the whole script, other errors are wrapped
+        macro_rules! import_error {
+            ($error:expr) => {
+                match $error {
+                    ModuleError::Runtime(e, _)
+                        if matches!(e.kind, InterpretErrorKind::Exit { .. }) =>
+                    {
+                        e
+                    }
+                    ModuleError::Runtime(e, message) => {
+                        runtime_error!(InterpretErrorKind::ImportError {
+                            message,
+                            cause: Some(Box::new(e.kind)),
+                        })
+                    }
+                    ModuleError::Load(message) => {
+                        runtime_error!(InterpretErrorKind::ImportError {
+                            message,
+                            cause: None
+                        })
+                    }
+                }
+            };
+        }
+
         macro_rules! comparison_operator {
             ($pat:pat) => {{
                 let second_operand = checked_stack_pop!()?;
@@ -338,15 +741,16 @@ impl<'gc, 'builtins> VM<'gc, 'builtins> {
                     }
 
                     (_, _) => {
-                        cast_binary!(&first_operand, +, &second_operand).ok_or_else(|| {
-                            runtime_error!(InterpretErrorKind::TypeError {
-                                message: format!(
-                                    "uncompatible types in Add (got {} and {})",
-                                    first_operand.type_string(),
-                                    second_operand.type_string()
-                                )
-                            })
-                        })?
+                        arithmetic_operator!(first_operand, +, IntegerOp::Add, second_operand)
+                            .ok_or_else(|| {
+                                runtime_error!(InterpretErrorKind::TypeError {
+                                    message: format!(
+                                        "uncompatible types in Add (got {} and {})",
+                                        first_operand.type_string(),
+                                        second_operand.type_string()
+                                    )
+                                })
+                            })?
                     }
                 };
                 self.stack.push(value);
@@ -354,12 +758,24 @@ impl<'gc, 'builtins> VM<'gc, 'builtins> {
                 InstructionExecution::NextInstruction
             }
 
+            Opcode::FormatString => {
+                let value = checked_stack_pop!()?;
+                let value = if value.unwrap_any_str().is_some() {
+                    value
+                } else {
+                    let formatted = crate::data::objects::pretty_format(&value, self);
+                    self.gc.new_string(&formatted)
+                };
+                self.stack.push(value);
+                InstructionExecution::NextInstruction
+            }
+
             Opcode::Sub => {
                 let second_operand = checked_stack_pop!()?;
                 let first_operand = checked_stack_pop!()?;
-                self.stack
-                    .push(
-                        cast_binary!(&first_operand, -, &second_operand).ok_or_else(|| {
+                self.stack.push(
+                    arithmetic_operator!(first_operand, -, IntegerOp::Sub, second_operand)
+                        .ok_or_else(|| {
                             runtime_error!(InterpretErrorKind::TypeError {
                                 message: format!(
                                     "uncompatible types in Sub (got {} and {})",
@@ -368,16 +784,16 @@ impl<'gc, 'builtins> VM<'gc, 'builtins> {
                                 )
                             })
                         })?,
-                    );
+                );
                 InstructionExecution::NextInstruction
             }
 
             Opcode::Mul => {
                 let second_operand = checked_stack_pop!()?;
                 let first_operand = checked_stack_pop!()?;
-                self.stack
-                    .push(
-                        cast_binary!(&first_operand, *, &second_operand).ok_or_else(|| {
+                self.stack.push(
+                    arithmetic_operator!(first_operand, *, IntegerOp::Mul, second_operand)
+                        .ok_or_else(|| {
                             runtime_error!(InterpretErrorKind::TypeError {
                                 message: format!(
                                     "uncompatible types in Multiply (got {} and {})",
@@ -386,7 +802,7 @@ impl<'gc, 'builtins> VM<'gc, 'builtins> {
                                 )
                             })
                         })?,
-                    );
+                );
                 InstructionExecution::NextInstruction
             }
 
@@ -394,15 +810,13 @@ impl<'gc, 'builtins> VM<'gc, 'builtins> {
                 let second_operand = checked_stack_pop!()?;
                 let first_operand = checked_stack_pop!()?;
 
-                if second_operand.unwrap_int().map(|i| i == 0i64) == Some(true)
-                    || second_operand.unwrap_float().map(|f| f.abs() == 0f64) == Some(true)
-                {
+                if value_ops::is_zero(&second_operand) {
                     return Err(runtime_error!(ZeroDivision));
                 }
 
-                self.stack
-                    .push(
-                        cast_binary!(&first_operand, /, &second_operand).ok_or_else(|| {
+                self.stack.push(
+                    arithmetic_operator!(first_operand, /, IntegerOp::Div, second_operand)
+                        .ok_or_else(|| {
                             runtime_error!(InterpretErrorKind::TypeError {
                                 message: format!(
                                     "uncompatible types in Divide (got {} and {})",
@@ -411,19 +825,120 @@ impl<'gc, 'builtins> VM<'gc, 'builtins> {
                                 )
                             })
                         })?,
-                    );
+                );
 
                 InstructionExecution::NextInstruction
             }
 
+            Opcode::FloorDiv => {
+                let second_operand = checked_stack_pop!()?;
+                let first_operand = checked_stack_pop!()?;
+
+                if value_ops::is_zero(&second_operand) {
+                    return Err(runtime_error!(ZeroDivision));
+                }
+
+                let value = match integer_binary(
+                    &first_operand,
+                    &second_operand,
+                    IntegerOp::FloorDiv,
+                    self.gc,
+                ) {
+                    Some(value) => value,
+                    None => match (numeric_cast(&first_operand), numeric_cast(&second_operand)) {
+                        (Some(first), Some(second)) => {
+                            (first.downgrade() / second.downgrade()).floor().into()
+                        }
+                        _ => {
+                            return Err(runtime_error!(InterpretErrorKind::TypeError {
+                                message: format!(
+                                    "uncompatible types in FloorDivide (got {} and {})",
+                                    first_operand.type_string(),
+                                    second_operand.type_string()
+                                )
+                            }))
+                        }
+                    },
+                };
+                self.stack.push(value);
+                InstructionExecution::NextInstruction
+            }
+
+            Opcode::BitAnd => integer_operator!(IntegerOp::BitAnd, "BitAnd"),
+            Opcode::BitOr => integer_operator!(IntegerOp::BitOr, "BitOr"),
+            Opcode::BitXor => integer_operator!(IntegerOp::BitXor, "BitXor"),
+
+            opcode @ (Opcode::ShiftLeft | Opcode::ShiftRight) => {
+                let count = get_from_top!()?;
+                if let Some(count) = value_ops::to_bigint(count) {
+                    if count.is_negative() {
+                        return Err(runtime_error!(InterpretErrorKind::TypeError {
+                            message: format!("negative shift count {}", count)
+                        }));
+                    }
+                    //operands of other types are reported by `integer_operator`
+                    let bits = self
+                        .stack
+                        .iter()
+                        .rev()
+                        .nth(1)
+                        .and_then(value_ops::integer_bits);
+                    if let (Opcode::ShiftLeft, Some(bits)) = (opcode, bits) {
+                        if !count.to_u64().is_some_and(|count| shift_fits(bits, count)) {
+                            return Err(runtime_error!(InterpretErrorKind::TypeError {
+                                message: format!("shift count {} is too large", count)
+                            }));
+                        }
+                    }
+                }
+                if opcode == Opcode::ShiftLeft {
+                    integer_operator!(IntegerOp::Shl, "ShiftLeft")
+                } else {
+                    integer_operator!(IntegerOp::Shr, "ShiftRight")
+                }
+            }
+
+            Opcode::BitNot => {
+                let value = checked_stack_pop!()?;
+                let result = value_ops::integer_not(&value, self.gc).ok_or_else(|| {
+                    runtime_error!(InterpretErrorKind::TypeError {
+                        message: format!("expected Int in BitNot, got {}", value.type_string())
+                    })
+                })?;
+                self.stack.push(result);
+                InstructionExecution::NextInstruction
+            }
+
+            Opcode::Negate => {
+                let value = checked_stack_pop!()?;
+                let result = value_ops::negate(&value, self.gc).ok_or_else(|| {
+                    runtime_error!(InterpretErrorKind::TypeError {
+                        message: format!("expected number in Negate, got {}", value.type_string())
+                    })
+                })?;
+                self.stack.push(result);
+                InstructionExecution::NextInstruction
+            }
+
             Opcode::Mod => {
-                let second_operand = as_int!(checked_stack_pop!()?)?;
-                let first_operand = as_int!(checked_stack_pop!()?)?;
+                let second_operand = checked_stack_pop!()?;
+                let first_operand = checked_stack_pop!()?;
+
+                for operand in [&first_operand, &second_operand] {
+                    if !operand.is_integer() {
+                        return Err(runtime_error!(TypeError {
+                            message: format!("expected Int but got {}", operand.type_string())
+                        }));
+                    }
+                }
+                if value_ops::is_zero(&second_operand) {
+                    return Err(runtime_error!(ZeroDivision));
+                }
 
-                let value = first_operand
-                    .checked_rem(second_operand)
-                    .ok_or(runtime_error!(ZeroDivision))?;
-                self.stack.push(Value::Int(value));
+                let value =
+                    integer_binary(&first_operand, &second_operand, IntegerOp::Rem, self.gc)
+                        .unwrap();
+                self.stack.push(value);
                 InstructionExecution::NextInstruction
             }
 
@@ -431,17 +946,22 @@ impl<'gc, 'builtins> VM<'gc, 'builtins> {
                 let second_operand = checked_stack_pop!()?;
                 let first_operand = checked_stack_pop!()?;
 
-                let value: Value =
+                let value: Value = if first_operand.is_integer() && second_operand.is_integer() {
+                    let exponent = second_operand
+                        .unwrap_int()
+                        .and_then(|b| u64::try_from(b).ok())
+                        .ok_or_else(|| {
+                            runtime_error!(InterpretErrorKind::NativeError {
+                                message: format!("unsupported operand {} in pow", second_operand)
+                            })
+                        })?;
+                    integer_pow(&first_operand, exponent, self.gc).ok_or_else(|| {
+                        runtime_error!(InterpretErrorKind::TypeError {
+                            message: format!("exponent {} is too large", exponent)
+                        })
+                    })?
+                } else {
                     match (numeric_cast(&first_operand), numeric_cast(&second_operand)) {
-                        (Some(NumberCastResult::Int(a)), Some(NumberCastResult::Int(b))) => {
-                            if b < 0 || b > u32::MAX as i64 {
-                                return Err(runtime_error!(InterpretErrorKind::NativeError {
-                                    message: format!("unsupported operand {} in pow", b)
-                                }));
-                            }
-                            a.pow(b as u64 as u32).into()
-                        }
-
                         (Some(first), Some(second)) => {
                             (first.downgrade().powf(second.downgrade())).into()
                         }
@@ -454,7 +974,8 @@ impl<'gc, 'builtins> VM<'gc, 'builtins> {
                                 )
                             }));
                         }
-                    };
+                    }
+                };
 
                 self.stack.push(value);
                 InstructionExecution::NextInstruction
@@ -478,49 +999,84 @@ impl<'gc, 'builtins> VM<'gc, 'builtins> {
             }
 
             Opcode::Import(idx) => {
-                let import = chunk
+                let (module, name) = chunk
                     .import_names
                     .get(idx as usize)
                     .ok_or(runtime_error!(OperandIndexing))?;
 
-                let module = &import.0;
-                let name = &import.1;
+                if is_private_name(name) {
+                    return Err(import_error!(ModuleError::Load(format!(
+                        "cannot import {} because it is private to module {}",
+                        name, module
+                    ))));
+                }
 
-                if !self.loaded_modules.contains_key(module) {
-                    use crate::execution::module::{self};
-                    use std::path::PathBuf;
-                    let path: PathBuf = (&import.0).into();
+                //global of module is preferred, `import a.b` falls back to submodule
+                let value = if self.module_exists(module) {
+                    self.load_module(module).map_err(|e| import_error!(e))?;
+                    match self.get_global(module, name) {
+                        Some(value) => value,
+                        None if self.module_exists(&module.submodule(name)) => self
+                            .import_module_object(&module.submodule(name))
+                            .map_err(|e| import_error!(e))?,
+                        None => {
+                            return Err(runtime_error!(InterpretErrorKind::NameError {
+                                name: name.clone()
+                            }))
+                        }
+                    }
+                } else if self.module_exists(&module.submodule(name)) {
+                    self.import_module_object(&module.submodule(name))
+                        .map_err(|e| import_error!(e))?
+                } else {
+                    //neither module nor submodule exists, report locations tried for both
+                    let message = [module.clone(), module.submodule(name)]
+                        .iter()
+                        .filter_map(|m| self.resolver.resolve(m).err())
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    return Err(import_error!(ModuleError::Load(message)));
+                };
 
-                    let state = self.save_stacks();
+                self.stack.push(value);
 
-                    //we want not to crash even if importing goes south
-                    self.locals_offset = self.stack.len();
+                InstructionExecution::NextInstruction
+            }
 
-                    let load_result = module::compile_file(path.as_path(), self)
-                        .and_then(|(src, ptr)| module::exec_with_error_printing(self, ptr, &src))
-                        .map_err(|e| e.to_string())
-                        .map_err(|e| {
-                            runtime_error!(InterpretErrorKind::ImportError { message: e })
-                        });
-                    self.load_stacks(state);
-                    let _ = load_result?;
-                }
+            Opcode::ImportModule(idx) => {
+                let (module, _) = chunk
+                    .import_names
+                    .get(idx as usize)
+                    .ok_or(runtime_error!(OperandIndexing))?;
 
                 let value = self
-                    .loaded_modules
-                    .get(module)
-                    .unwrap()
-                    .get(name)
-                    .cloned()
-                    .ok_or_else(|| {
-                        runtime_error!(InterpretErrorKind::NameError { name: name.clone() })
-                    })?;
-
+                    .import_module_object(module)
+                    .map_err(|e| import_error!(e))?;
                 self.stack.push(value);
 
                 InstructionExecution::NextInstruction
             }
 
+            Opcode::ImportAll(idx) => {
+                let (module, _) = chunk
+                    .import_names
+                    .get(idx as usize)
+                    .ok_or(runtime_error!(OperandIndexing))?;
+
+                self.load_module(module).map_err(|e| import_error!(e))?;
+                let globals = self.loaded_modules[module]
+                    .iter()
+                    .filter(|(name, _)| !is_private_name(name))
+                    .map(|(name, value)| (name.clone(), value.clone()))
+                    .collect::<Vec<_>>();
+                self.loaded_modules
+                    .get_mut(&chunk.module)
+                    .unwrap()
+                    .extend(globals);
+
+                InstructionExecution::NextInstruction
+            }
+
             Opcode::LoadField(idx) => {
                 let pointer = checked_stack_pop!()?;
                 let key = checked_get_name!(idx)?;
@@ -531,7 +1087,7 @@ impl<'gc, 'builtins> VM<'gc, 'builtins> {
                     }
                     None => {
                         return Err(runtime_error!(InterpretErrorKind::AttributeError {
-                            object: pointer,
+                            object_type: pointer.type_string(),
                             missed_field: key.to_string()
                         }))
                     }
@@ -549,7 +1105,7 @@ impl<'gc, 'builtins> VM<'gc, 'builtins> {
                     Ok(()) => {}
                     Err(()) => {
                         return Err(runtime_error!(InterpretErrorKind::AttributeError {
-                            object: pointer,
+                            object_type: pointer.type_string(),
                             missed_field: key.to_string()
                         }))
                     }
@@ -567,7 +1123,7 @@ impl<'gc, 'builtins> VM<'gc, 'builtins> {
                     }
                     None => {
                         return Err(runtime_error!(InterpretErrorKind::IndexAttributeError {
-                            object: pointer,
+                            object_type: pointer.type_string(),
                             missed_idx: idx as usize
                         }))
                     }
@@ -585,7 +1141,7 @@ impl<'gc, 'builtins> VM<'gc, 'builtins> {
                     }
                     None => {
                         return Err(runtime_error!(InterpretErrorKind::IndexAttributeError {
-                            object: pointer,
+                            object_type: pointer.type_string(),
                             missed_idx: idx as usize
                         }))
                     }
@@ -715,6 +1271,43 @@ impl<'gc, 'builtins> VM<'gc, 'builtins> {
                 InstructionExecution::LocalJump(new_ip)
             }
 
+            Opcode::JumpBack(delta) => {
+                let new_ip = ip
+                    .checked_sub(delta as usize)
+                    .ok_or_else(|| runtime_error!(JumpBounds))?;
+                InstructionExecution::LocalJump(new_ip)
+            }
+
+            Opcode::GetIter => {
+                let value = checked_stack_pop!()?;
+
+                let iterator = builtin_result!(self.make_iterator(value))?;
+
+                self.stack.push(iterator);
+                InstructionExecution::NextInstruction
+            }
+
+            Opcode::ForIter(delta) => {
+                //iterator stays on stack and items are not held across calls, so collection
+                //is allowed here (generator bodies may run for long between yields)
+                let iterator = get_from_top!()?.clone();
+
+                match builtin_result!(self.iterator_next(&iterator))? {
+                    Some(item) => {
+                        self.stack.push(item);
+                        InstructionExecution::NextInstruction
+                    }
+                    None => {
+                        self.stack.pop();
+                        let new_ip = ip + delta as usize;
+                        if new_ip >= chunk.code.len() {
+                            return Err(runtime_error!(JumpBounds));
+                        }
+                        InstructionExecution::LocalJump(new_ip)
+                    }
+                }
+            }
+
             Opcode::Pop(n) => {
                 if self.stack.len() < n as usize {
                     return Err(runtime_error!(StackUnderflow));
@@ -832,12 +1425,7 @@ impl<'gc, 'builtins> VM<'gc, 'builtins> {
 
                         let builtins = self.builtins;
 
-                        let result = builtins.apply_builtin(*name, args, self);
-                        let result = result.map_err(|e| {
-                            runtime_error!(InterpretErrorKind::NativeError {
-                                message: e.to_string()
-                            })
-                        })?;
+                        let result = builtin_result!(builtins.apply_builtin(*name, args, self))?;
                         self.stack.push(result);
                         InstructionExecution::NextInstruction
                     }
@@ -859,14 +1447,9 @@ impl<'gc, 'builtins> VM<'gc, 'builtins> {
 
                         let builtins = self.builtins;
 
-                        let result =
-                            builtins.apply_method(class_idx, method_idx, self_ptr, args, self);
-
-                        let result = result.map_err(|e| {
-                            runtime_error!(InterpretErrorKind::NativeError {
-                                message: e.to_string()
-                            })
-                        })?;
+                        let result = builtin_result!(
+                            builtins.apply_method(class_idx, method_idx, self_ptr, args, self)
+                        )?;
 
                         self.stack.push(result);
                         InstructionExecution::NextInstruction
@@ -904,6 +1487,18 @@ impl<'gc, 'builtins> VM<'gc, 'builtins> {
                             })
                         })?;
 
+                        if new_chunk.unwrap_function().unwrap().is_generator {
+                            //body is not run until first `next`, frame is kept in generator
+                            let frame = self.stack.split_off(self.stack.len() - 1 - arity);
+                            let generator = self.gc.store(NativeIterator::Generator {
+                                function: new_chunk,
+                                frame,
+                                ip: 0,
+                            });
+                            self.stack.push(generator);
+                            return Ok(InstructionExecution::NextInstruction);
+                        }
+
                         self.call_stack.push(CallStackValue {
                             return_chunk: current_chunk.clone(),
                             return_ip: ip + 1,
@@ -941,7 +1536,7 @@ impl<'gc, 'builtins> VM<'gc, 'builtins> {
 
                 let target_arity = target.get_arity(self).unwrap();
 
-                if !target_arity.accepts(arity as usize) {
+                if !target_arity.accepts(arity) {
                     return Err(runtime_error!(TypeError {
                         message: format!("expected {} but got {} args", target_arity, arity)
                     }));
@@ -958,8 +1553,17 @@ impl<'gc, 'builtins> VM<'gc, 'builtins> {
                 InstructionExecution::NextInstruction
             }
 
+            Opcode::Yield => {
+                if !chunk.is_generator || self.call_stack.len() != self.frame_base + 1 {
+                    return Err(runtime_error!(TypeError {
+                        message: "yield outside of generator".to_string()
+                    }));
+                }
+                InstructionExecution::Suspension
+            }
+
             Opcode::Return => {
-                if self.call_stack.len() <= 1 {
+                if self.call_stack.len() <= self.frame_base + 1 {
                     return Ok(InstructionExecution::Termination);
                 }
                 let return_info = self.call_stack.pop().unwrap();
@@ -1117,6 +1721,10 @@ impl<'gc, 'builtins> VM<'gc, 'builtins> {
                 instance.fields.get_index_mut(index).map(|(_k, v)| v)
             }
 
+            obj @ StackObject::HeapObject(..) if obj.unwrap_vector().is_some() => {
+                obj.unwrap_vector().unwrap().get_mut(index)
+            }
+
             _other => None,
         }
     }
@@ -1160,3 +1768,469 @@ impl<'gc, 'builtins> VM<'gc, 'builtins> {
         None
     }
 }
+
+/// copies methods of reloaded struct or enum descriptor `new` into its previous version `old`,
+/// returns whether `old` may stand in for `new`. Descriptors whose fields (or variants) changed
+/// are left alone, as their methods may read fields old instances do not have
+fn rebind_descriptor(old: &Value, new: &Value) -> bool {
+    match (old.as_heap_object(), new.as_heap_object()) {
+        //global imported from other module is not reloaded
+        (Some(old), Some(new)) if std::ptr::eq(old, new) => return true,
+        (Some(_), Some(_)) => {}
+        _ => return false,
+    }
+    if !same_shape(old, new) {
+        return false;
+    }
+
+    if let (Some(old), Some(new)) = (
+        old.unwrap_struct_descriptor(),
+        new.unwrap_struct_descriptor(),
+    ) {
+        old.methods = new.methods.clone();
+    }
+
+    if let (Some(old), Some(new)) = (old.unwrap_enum_descriptor(), new.unwrap_enum_descriptor()) {
+        old.methods = new.methods.clone();
+        for (name, new_variant) in &new.variants {
+            rebind_descriptor(&old.variants[name], new_variant);
+        }
+    }
+    true
+}
+
+/// checks that struct or enum descriptors have the same fields and variants
+fn same_shape(old: &Value, new: &Value) -> bool {
+    if let (Some(old), Some(new)) = (
+        old.unwrap_struct_descriptor(),
+        new.unwrap_struct_descriptor(),
+    ) {
+        return old.fields == new.fields;
+    }
+
+    if let (Some(old), Some(new)) = (old.unwrap_enum_descriptor(), new.unwrap_enum_descriptor()) {
+        return old.variants.len() == new.variants.len()
+            && new.variants.iter().all(|(name, new_variant)| {
+                old.variants
+                    .get(name)
+                    .is_some_and(|old_variant| same_shape(old_variant, new_variant))
+            });
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod test {
+    use super::{InterpretErrorKind, VM};
+    use crate::data::conversion;
+    use crate::data::objects::Value;
+    use crate::execution::module::{compile_program, Module};
+    use crate::test::{assert_errors, run_program, test_module, with_vm};
+
+    const PROGRAM: &str = "
+struct Pair:
+    first
+    second
+
+impl Pair:
+    def sum(self) = self.first + self.second
+
+def add(a, b) = a + b
+
+def make_adder(n) = (x) => x + n
+
+def pack(first, *rest) = rest
+
+def fail(x) =
+    assert x
+    x
+
+var add_one = add(1, _)
+";
+
+    fn with_program(test: impl FnOnce(&mut VM, &Module)) {
+        with_vm(|vm| {
+            run_program(vm, PROGRAM).unwrap();
+            test(vm, &test_module());
+        });
+    }
+
+    #[test]
+    fn vm_should_call_functions() {
+        with_program(|vm, module| {
+            let add = vm.get_global(module, "add").unwrap();
+            for i in 0..100 {
+                let result = vm.call(add.clone(), vec![Value::Int(i), Value::Int(2)]);
+                assert_eq!(result.unwrap(), Value::Int(i + 2));
+            }
+            assert!(vm.stack.is_empty());
+            assert!(vm.call_stack.is_empty());
+        });
+    }
+
+    #[test]
+    fn vm_should_call_closures_and_partials() {
+        with_program(|vm, module| {
+            let make_adder = vm.get_global(module, "make_adder").unwrap();
+            let adder = vm.call(make_adder, vec![Value::Int(10)]).unwrap();
+            assert_eq!(vm.call(adder, vec![Value::Int(5)]).unwrap(), Value::Int(15));
+
+            let add_one = vm.get_global(module, "add_one").unwrap();
+            assert_eq!(
+                vm.call(add_one, vec![Value::Int(5)]).unwrap(),
+                Value::Int(6)
+            );
+        });
+    }
+
+    #[test]
+    fn vm_should_call_builtins_and_structs() {
+        with_program(|vm, module| {
+            let int = vm.builtins.get_builtin("int").unwrap();
+            let s = vm.gc.new_string("42");
+            assert_eq!(vm.call(int, vec![s]).unwrap(), Value::Int(42));
+
+            let pair = vm.get_global(module, "Pair").unwrap();
+            let instance = vm.call(pair, vec![Value::Int(1), Value::Int(2)]).unwrap();
+            let sum = instance.lookup("sum", vm).unwrap();
+            assert_eq!(vm.call(sum, vec![]).unwrap(), Value::Int(3));
+        });
+    }
+
+    #[test]
+    fn vm_should_pack_varargs() {
+        with_program(|vm, module| {
+            let pack = vm.get_global(module, "pack").unwrap();
+            let args = vec![Value::Int(1), Value::Int(2), Value::Int(3)];
+            let rest = vm.call(pack, args).unwrap();
+            assert_eq!(
+                rest.unwrap_vector().unwrap(),
+                &vec![Value::Int(2), Value::Int(3)]
+            );
+        });
+    }
+
+    #[test]
+    fn huge_shifts_should_be_reported() {
+        assert_errors([
+            (
+                "var n = 100000000000\n1 << n",
+                "TypeError { message: \"shift count 100000000000 is too large\" }",
+            ),
+            (
+                "var n = 1 << 70\n1 << n",
+                "shift count 1180591620717411303424 is too large",
+            ),
+            (
+                "var n = 1048575\nvar x = 1 << n\nx << 1",
+                "shift count 1 is too large",
+            ),
+            (
+                "var n = -1\n1 << n",
+                "TypeError { message: \"negative shift count -1\" }",
+            ),
+            ("var n = -1\n1 >> n", "negative shift count -1"),
+            ("var n = 1\n\"a\" << n", "uncompatible types in ShiftLeft"),
+        ]);
+
+        //right shifts of any length are fine
+        with_vm(|vm| {
+            let result = run_program(vm, "var n = 1 << 70\n-5 >> n");
+            assert_eq!(result.unwrap(), Value::Int(-1));
+        });
+    }
+
+    #[test]
+    fn huge_powers_should_be_reported() {
+        assert_errors([
+            (
+                "var n = 100000000000\n10 ** n",
+                "TypeError { message: \"exponent 100000000000 is too large\" }",
+            ),
+            ("var n = 524289\n3 ** n", "exponent 524289 is too large"),
+            ("var n = 1 << 70\n2 ** n", "unsupported operand"),
+        ]);
+
+        //powers of 0, 1 and -1 never grow
+        with_vm(|vm| {
+            let program =
+                "var n = 100000000001\nvar m = -1\nlist(0 ** n, 1 ** n, m ** n, m ** (n + 1))";
+            let result = run_program(vm, program).unwrap();
+            let result: Vec<i64> = conversion::from_value(&result).unwrap();
+            assert_eq!(result, vec![0, 1, -1, 1]);
+        });
+    }
+
+    #[test]
+    fn vm_should_report_errors_from_calls() {
+        with_program(|vm, module| {
+            let fail = vm.get_global(module, "fail").unwrap();
+            let error = vm.call(fail.clone(), vec![Value::Bool(false)]).unwrap_err();
+            assert_eq!(error.kind, InterpretErrorKind::AssertionFailure);
+
+            //vm stays usable after error
+            assert_eq!(
+                vm.call(fail, vec![Value::Bool(true)]).unwrap(),
+                Value::Bool(true)
+            );
+            assert!(vm.stack.is_empty());
+
+            let error = vm.call(Value::Int(1), vec![]).unwrap_err();
+            assert!(matches!(error.kind, InterpretErrorKind::TypeError { .. }));
+
+            let add = vm.get_global(module, "add").unwrap();
+            let error = vm.call(add, vec![Value::Int(1); 70000]).unwrap_err();
+            assert_eq!(
+                error.kind,
+                InterpretErrorKind::TypeError {
+                    message: "too many arguments in call: 70000".to_string()
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn garbage_should_be_collected_while_builtins_run() {
+        with_program(|vm, _| {
+            let program = "
+struct Garbage:
+    item
+
+def work(n) =
+    for i in range(n)
+        var g = Garbage(list(i))
+        g._0 = g
+    list(n)
+
+list(3000, 4000).map(work)
+";
+            let result = run_program(vm, program).unwrap();
+
+            //result of first call is only referenced by `map` while second one runs
+            let result: Vec<Vec<i64>> = conversion::from_value(&result).unwrap();
+            assert_eq!(result, vec![vec![3000], vec![4000]]);
+            assert!(vm.gc.items().count() < 3000);
+        });
+    }
+
+    #[test]
+    fn recursion_through_builtins_should_overflow() {
+        //test threads have small stacks, while hosts run on main thread
+        let tester = std::thread::Builder::new().stack_size(32 * 1024 * 1024);
+        let test = tester.spawn(|| {
+            with_program(|vm, module| {
+                let program = "
+def f(x) = list(1).map(f)
+
+def gen(n) =
+    for x in gen(n)
+        yield x
+
+f(0)
+";
+                let error = run_program(vm, program).unwrap_err();
+                assert_eq!(*error.kind.root_cause(), InterpretErrorKind::StackOverflow);
+
+                let gen = vm.get_global(module, "gen").unwrap();
+                let generator = vm.call(gen, vec![Value::Int(0)]).unwrap();
+                let error = vm.iterator_next(&generator).unwrap_err();
+                assert!(error.to_string().contains("StackOverflow"), "{}", error);
+
+                //vm stays usable after overflow
+                let f = vm.get_global(module, "f").unwrap();
+                assert!(vm.call(f, vec![Value::Int(0)]).is_err());
+                assert!(vm.stack.is_empty());
+                assert_eq!(vm.native_depth, 0);
+            })
+        });
+        test.unwrap().join().unwrap();
+    }
+
+    #[test]
+    fn imports_should_report_errors() {
+        let cases = [
+            (
+                "import std.loops.missing",
+                "NameError { name: \"missing\" }",
+            ),
+            (
+                "from lib.greeting import greet, _punctuation",
+                "cannot import _punctuation because it is private to module lib.greeting",
+            ),
+            (
+                "import lib.greeting\ngreeting._punctuation",
+                "missed_field: \"_punctuation\"",
+            ),
+            (
+                "import std.missing.x",
+                "module std.missing.x not found, tried:",
+            ),
+            (
+                "from std.missing import *",
+                "module std.missing not found, tried:",
+            ),
+            ("import std.option\noption.x = 1", "missed_field: \"x\""),
+            (
+                "def f =\n    from std.loops import *\n    1",
+                "wildcard import is only allowed at module level [2:27]",
+            ),
+            (
+                "import .lib.greeting",
+                "relative import .lib escapes package of module `TEST` [1:13]",
+            ),
+            (
+                "from .. import *",
+                "relative import .. escapes package of module `TEST` [1:16]",
+            ),
+        ];
+        with_vm(|vm| {
+            vm.resolver.set_script_dir("examples");
+            for (program, expected) in cases {
+                let error = match compile_program(program.to_string(), &test_module(), vm) {
+                    Ok(pointer) => format!("{:?}", vm.run(pointer).unwrap_err()),
+                    Err(e) => e.to_string(),
+                };
+                assert!(error.contains(expected), "{}: {}", program, error);
+            }
+
+            //misspelled module is reported together with fallback lookup of submodule
+            let expected = [
+                "module std.loopz not found, tried:",
+                "    <embedded stdlib>/std/loopz.txt",
+                "module std.loopz.for_each not found, tried:",
+                "    <embedded stdlib>/std/loopz/for_each.txt",
+            ];
+            let error = run_program(vm, "import std.loopz.for_each").unwrap_err();
+            match error.kind {
+                InterpretErrorKind::ImportError { message, cause } => {
+                    assert_eq!(message, expected.join("\n"));
+                    assert_eq!(cause, None);
+                }
+                other => panic!("expected ImportError, got {:?}", other),
+            }
+        });
+    }
+
+    #[test]
+    fn errors_of_imported_modules_should_keep_their_kind() {
+        with_vm(|vm| {
+            vm.resolver.set_script_dir("examples");
+
+            let error = run_program(vm, "import failing.exit.x").unwrap_err();
+            assert_eq!(error.kind, InterpretErrorKind::Exit { code: 6 });
+
+            let error = run_program(vm, "from failing import assertion").unwrap_err();
+            assert!(
+                matches!(&error.kind, InterpretErrorKind::ImportError { message, .. } if message.contains("assert 1 == 2")),
+                "{:?}",
+                error.kind
+            );
+            assert_eq!(
+                error.kind.root_cause(),
+                &InterpretErrorKind::AssertionFailure
+            );
+        });
+    }
+
+    #[test]
+    fn circular_imports_should_be_reported() {
+        with_vm(|vm| {
+            vm.resolver.set_script_dir("examples");
+
+            let error = format!("{:?}", run_program(vm, "import cycle.a.first").unwrap_err());
+            assert!(
+                error.contains("circular import: cycle.a -> cycle.b -> cycle.a"),
+                "{}",
+                error
+            );
+
+            //modules that failed to load are forgotten
+            assert!(vm.loading_modules.is_empty());
+            for name in ["cycle.a", "cycle.b"] {
+                assert!(!vm
+                    .loaded_modules
+                    .contains_key(&Module::from_dot_notation(name)));
+            }
+        });
+    }
+
+    #[test]
+    fn reloaded_module_should_update_globals_and_methods() {
+        let dir = std::env::temp_dir().join(format!("blop_reload_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("hot")).unwrap();
+
+        let write_module = |version: &str, fields: &str| {
+            let source = format!(
+                "struct Point:\n    {}\n\nimpl Point:\n    def describe(self) = \"{}\"\n\nvar version = \"{}\"\n",
+                fields, version, version
+            );
+            std::fs::write(dir.join("hot").join("shapes.txt"), source).unwrap();
+        };
+        let shapes = Module::from_dot_notation("hot.shapes");
+
+        with_vm(|vm| {
+            vm.resolver.set_script_dir(&dir);
+
+            write_module("v1", "x\n    y");
+            run_program(vm, "import hot.shapes\nvar p = shapes.Point(1, 2)").unwrap();
+
+            write_module("v2", "x\n    y");
+            vm.reload_module(&shapes, true).unwrap();
+            let result = run_program(vm, "f\"{shapes.version} {p.describe()}\"");
+            assert_eq!(format!("{}", result.unwrap()), "v2 v2");
+            //descriptor keeps its identity as fields did not change
+            let instance = vm.get_global(&test_module(), "p").unwrap();
+            let descriptor = instance
+                .unwrap_struct_instance()
+                .unwrap()
+                .descriptor
+                .clone();
+            let point = vm.get_global(&shapes, "Point").unwrap();
+            assert!(std::ptr::eq(
+                descriptor.as_heap_object().unwrap(),
+                point.as_heap_object().unwrap()
+            ));
+
+            //struct with different fields is replaced, old instances keep methods they had
+            write_module("v3", "x");
+            run_program(vm, "reload(shapes)").unwrap();
+            let result = run_program(vm, "f\"{p.describe()} {shapes.Point(1).describe()}\"");
+            assert_eq!(format!("{}", result.unwrap()), "v2 v3");
+            let point = vm.get_global(&shapes, "Point").unwrap();
+            assert!(!std::ptr::eq(
+                descriptor.as_heap_object().unwrap(),
+                point.as_heap_object().unwrap()
+            ));
+            assert_eq!(
+                descriptor.unwrap_struct_descriptor().unwrap().fields,
+                ["x", "y"]
+            );
+
+            //module that fails to compile keeps previous version
+            std::fs::write(dir.join("hot").join("shapes.txt"), "var x = (").unwrap();
+            assert!(vm.reload_module(&shapes, true).is_err());
+            assert_eq!(
+                format!("{}", run_program(vm, "shapes.version").unwrap()),
+                "v3"
+            );
+
+            let error = vm.reload_module(&Module::from_dot_notation("math"), true);
+            assert_eq!(
+                error.unwrap_err().to_string(),
+                "native module math cannot be reloaded"
+            );
+
+            //`exit` in reloaded module is not turned into error of `reload`
+            std::fs::write(dir.join("hot").join("shapes.txt"), "exit(9)").unwrap();
+            let error = run_program(vm, "reload(shapes)").unwrap_err();
+            assert_eq!(error.kind, InterpretErrorKind::Exit { code: 9 });
+            assert_eq!(
+                format!("{}", run_program(vm, "shapes.version").unwrap()),
+                "v3"
+            );
+        });
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,538 @@
+use std::cell::{RefCell, RefMut};
+use std::fmt::{Debug, Display, Write};
+use std::mem::ManuallyDrop;
+use std::path::{Path, PathBuf};
+use std::ptr::NonNull;
+
+use crate::data::conversion::{self, ConversionError};
+use crate::data::gc::GC;
+use crate::data::objects::Value;
+use crate::execution::arity::Arity;
+use crate::execution::builtins::{builtin_factory, BuiltinMap};
+use crate::execution::chunk::Opcode;
+use crate::execution::manifest::{Manifest, MANIFEST_FILE};
+use crate::execution::module::{self, Module, MAIN_FLAG, MAIN_FUNCTION};
+use crate::execution::resolver::ModuleResolver;
+use crate::execution::vm::{InterpretError, InterpretErrorKind, ModuleError, VM};
+
+/// owns everything needed to run BLOP code: garbage collector, builtins and VM that borrows them
+pub struct Interpreter {
+    vm: ManuallyDrop<RefCell<VM<'static, 'static>>>,
+    gc: NonNull<GC>,
+    builtins: NonNull<BuiltinMap>,
+}
+
+/// BLOP value held by host. Handle borrows interpreter that created it, so it can not outlive
+/// its garbage collector, and value it holds is never collected while handle is alive
+///
+/// ```compile_fail
+/// let value = {
+///     let interpreter = blop::Interpreter::new();
+///     interpreter.to_value(&vec![1, 2]).unwrap()
+/// };
+/// ```
+#[derive(Clone)]
+pub struct Handle<'i> {
+    value: Value,
+    interpreter: &'i Interpreter,
+}
+
+impl PartialEq for Handle<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl PartialEq<Value> for Handle<'_> {
+    fn eq(&self, other: &Value) -> bool {
+        self.value == *other
+    }
+}
+
+impl Display for Handle<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+impl Debug for Handle<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.value)
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// source could not be read, parsed or compiled
+    Compile(String),
+    /// error raised during execution together with its human-readable description
+    Runtime(InterpretErrorKind, String),
+    /// script called `exit(code)`
+    Exit(i32),
+}
+
+/// process exit code of script that failed to compile
+pub const EXIT_COMPILE_ERROR: i32 = 2;
+/// process exit code of script that raised an error
+pub const EXIT_RUNTIME_ERROR: i32 = 1;
+/// process exit code of script with failed `assert`
+pub const EXIT_ASSERTION_FAILURE: i32 = 3;
+
+impl Error {
+    /// process exit code the command line interpreter reports for this error
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Compile(_) => EXIT_COMPILE_ERROR,
+            Error::Runtime(kind, _)
+                if matches!(kind.root_cause(), InterpretErrorKind::AssertionFailure) =>
+            {
+                EXIT_ASSERTION_FAILURE
+            }
+            Error::Runtime(..) => EXIT_RUNTIME_ERROR,
+            Error::Exit(code) => *code,
+        }
+    }
+
+    fn from_runtime(error: InterpretError, source: &str) -> Self {
+        match error.kind {
+            InterpretErrorKind::Exit { code } => Error::Exit(code),
+            _ => {
+                let kind = error.kind.clone();
+                Error::Runtime(kind, display_error(source, error))
+            }
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Compile(message) => write!(f, "{}", message),
+            Error::Runtime(_, message) => write!(f, "{}", message),
+            Error::Exit(code) => write!(f, "exit({})", code),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Interpreter {
+    /// creates interpreter with default set of builtins
+    pub fn new() -> Self {
+        //default builtins do not keep values they are called with
+        unsafe { Self::with_builtins(builtin_factory()) }
+    }
+
+    /// creates interpreter with custom builtins (e.g. `builtin_factory()` extended with
+    /// native modules of host application)
+    ///
+    /// # Safety
+    ///
+    /// Native functions receive raw [`Value`]s that point into garbage collector of this
+    /// interpreter. They should not keep these values (or values they create) after they return,
+    /// e.g. in captured state or statics, and should not pass them to another interpreter:
+    /// such values would be freed together with their garbage collector
+    pub unsafe fn with_builtins(builtins: BuiltinMap) -> Self {
+        //objects keep pointer to their GC, so it should never move after creation
+        let gc = NonNull::from(Box::leak(Box::new(unsafe { GC::default_gc() })));
+        let builtins = NonNull::from(Box::leak(Box::new(builtins)));
+
+        let vm = unsafe { VM::new(&mut *gc.as_ptr(), &*builtins.as_ptr()) };
+
+        Interpreter {
+            vm: ManuallyDrop::new(RefCell::new(vm)),
+            gc,
+            builtins,
+        }
+    }
+
+    /// VM is only handed out to native functions (see [`Interpreter::with_builtins`]): with
+    /// `&mut VM` code could swap GCs of two interpreters, while objects keep raw pointers to the
+    /// GC that allocated them
+    fn vm(&self) -> RefMut<'_, VM<'static, 'static>> {
+        self.vm.borrow_mut()
+    }
+
+    fn handle(&self, value: Value) -> Handle<'_> {
+        Handle {
+            value,
+            interpreter: self,
+        }
+    }
+
+    /// values of other interpreters would be freed together with their GC
+    fn unwrap_handle(&self, handle: Handle) -> Value {
+        assert!(
+            std::ptr::eq(handle.interpreter, self),
+            "value belongs to another interpreter"
+        );
+        handle.value
+    }
+
+    pub fn builtins(&self) -> &BuiltinMap {
+        unsafe { self.builtins.as_ref() }
+    }
+
+    /// search path of imported modules, see [`ModuleResolver`]
+    pub fn resolver_mut(&mut self) -> &mut ModuleResolver {
+        &mut self.vm.get_mut().resolver
+    }
+
+    /// adds directory to module search path, after `BLOP_PATH` entries
+    pub fn add_include_dir(&mut self, dir: impl Into<PathBuf>) {
+        self.resolver_mut().add_include_dir(dir);
+    }
+
+    pub fn compile(&self, source: &str, module: &Module) -> Result<Handle<'_>, Error> {
+        module::compile_program(source.to_string(), module, &mut self.vm())
+            .map(|entry_point| self.handle(entry_point))
+            .map_err(|e| Error::Compile(e.to_string()))
+    }
+
+    /// compiles file returning its source (used for error reporting) and entry point
+    pub fn compile_file(&self, path: &Path) -> Result<(String, Handle<'_>), Error> {
+        module::compile_file(path, &mut self.vm())
+            .map(|(source, entry_point)| (source, self.handle(entry_point)))
+            .map_err(|e| Error::Compile(e.to_string()))
+    }
+
+    /// runs compiled entry point. `source` is only used to describe errors
+    pub fn run(&self, entry_point: Handle, source: &str) -> Result<Handle<'_>, Error> {
+        let entry_point = self.unwrap_handle(entry_point);
+        let result = self.vm().run(entry_point);
+        result
+            .map(|value| self.handle(value))
+            .map_err(|e| Error::from_runtime(e, source))
+    }
+
+    /// compiles and runs source as entry module, so its `__main__` is true
+    pub fn run_source(&self, source: &str, module: &Module) -> Result<Handle<'_>, Error> {
+        let entry_point = self.compile(source, module)?;
+        self.vm().set_global(module, MAIN_FLAG, true.into());
+        self.run(entry_point, source)
+    }
+
+    pub fn run_file(&self, path: &Path) -> Result<Handle<'_>, Error> {
+        let (source, entry_point) = self.compile_file(path)?;
+        self.run(entry_point, &source)
+    }
+
+    /// runs script like the command line interpreter: executes the file, then calls its `main`
+    /// function (if defined) with `args` (also returned by `args()` builtin). `main` is not called
+    /// when script refers to it on its own, e.g. ends with `main(args())`, and when global `main`
+    /// is not a function. Returns result of `main`, or of script without it
+    pub fn run_main(&self, path: &Path, args: &[String]) -> Result<Handle<'_>, Error> {
+        self.vm().script_args = args.to_vec();
+        let (source, entry_point) = self.compile_file(path)?;
+        let calls_main = entry_point
+            .value
+            .unwrap_function()
+            .is_some_and(|chunk| module::refers_to_main(chunk));
+        let result = self.run(entry_point, &source)?;
+        if calls_main {
+            return Ok(result);
+        }
+
+        let module = module::script_module(path).map_err(|e| Error::Compile(e.to_string()))?;
+        let main = match self.get_global(&module, MAIN_FUNCTION) {
+            Some(main) if main.value.unwrap_function().is_some() => main,
+            Some(main) if main.value.unwrap_closure().is_some() => main,
+            _ => return Ok(result),
+        };
+        let arity = main.value.get_arity(&mut self.vm());
+        let args = match arity {
+            Some(Arity::Exact(0)) => vec![],
+            _ => vec![self.to_value(args).unwrap()],
+        };
+        let result = self
+            .vm()
+            .call(main.value, args.into_iter().map(|arg| arg.value).collect());
+        result
+            .map(|value| self.handle(value))
+            .map_err(|e| Error::from_runtime(e, &source))
+    }
+
+    /// runs entry point of project whose manifest is in `dir` or one of its parents,
+    /// dependencies of project become importable packages. `args` are passed to `main`
+    pub fn run_project(&self, dir: &Path, args: &[String]) -> Result<Handle<'_>, Error> {
+        let root = Manifest::find(dir).ok_or_else(|| {
+            Error::Compile(format!("{} not found in {}", MANIFEST_FILE, dir.display()))
+        })?;
+        let manifest = Manifest::load(&root).map_err(Error::Compile)?;
+        manifest
+            .configure(&mut self.vm().resolver)
+            .map_err(Error::Compile)?;
+        let entry_point = manifest.entry_point().map_err(Error::Compile)?;
+        self.run_main(&entry_point, args)
+    }
+
+    /// recompiles module from source and replaces its globals. With `rebind_methods`, live
+    /// instances of its structs and enums pick up new methods, unless their fields changed
+    pub fn reload_module(&self, module: &Module, rebind_methods: bool) -> Result<(), Error> {
+        let result = self.vm().reload_module(module, rebind_methods);
+        result.map_err(|e| match e {
+            ModuleError::Load(message) => Error::Compile(message),
+            ModuleError::Runtime(e, message) => match e.kind {
+                InterpretErrorKind::Exit { code } => Error::Exit(code),
+                kind => Error::Runtime(kind, message),
+            },
+        })
+    }
+
+    /// calls any callable value (function, closure, partial, builtin, builtin method or struct
+    /// descriptor) with provided arguments and returns call result
+    pub fn call(&self, callable: Handle, args: Vec<Handle>) -> Result<Handle<'_>, Error> {
+        let callable = self.unwrap_handle(callable);
+        let args = args
+            .into_iter()
+            .map(|arg| self.unwrap_handle(arg))
+            .collect();
+        let result = self.vm().call(callable, args);
+        result
+            .map(|value| self.handle(value))
+            .map_err(|e| Error::from_runtime(e, ""))
+    }
+
+    /// converts any serializable Rust value into BLOP value
+    pub fn to_value<T: serde::Serialize + ?Sized>(
+        &self,
+        value: &T,
+    ) -> Result<Handle<'_>, ConversionError> {
+        let value = self.vm().to_value(value)?;
+        Ok(self.handle(value))
+    }
+
+    /// converts BLOP value into Rust value, see [`from_value`](crate::from_value)
+    pub fn from_value<T: serde::de::DeserializeOwned>(
+        &self,
+        value: &Handle,
+    ) -> Result<T, ConversionError> {
+        conversion::from_value(&value.value)
+    }
+
+    /// looks up global variable `name` defined by (already loaded) module
+    pub fn get_global(&self, module: &Module, name: &str) -> Option<Handle<'_>> {
+        let value = self.vm().get_global(module, name)?;
+        Some(self.handle(value))
+    }
+
+    /// defines or overwrites global variable `name` of module
+    pub fn set_global(&self, module: &Module, name: &str, value: Handle) {
+        let value = self.unwrap_handle(value);
+        self.vm().set_global(module, name, value)
+    }
+}
+
+impl Drop for Interpreter {
+    fn drop(&mut self) {
+        //VM holds values that reference GC, so it should be dropped first. Native closures
+        //may capture values as well
+        unsafe {
+            ManuallyDrop::drop(&mut self.vm);
+            drop(Box::from_raw(self.builtins.as_ptr()));
+            drop(Box::from_raw(self.gc.as_ptr()));
+        }
+    }
+}
+
+/// formats runtime error, pointing to failed instruction and line of `source`
+pub(crate) fn display_error(source: &str, error: InterpretError) -> String {
+    let mut result = String::new();
+    writeln!(result, "error: {:?}", error.kind).unwrap();
+    let instruction: Opcode = error.chunk.unwrap_function().unwrap().code[error.opcode_index];
+    writeln!(
+        result,
+        "    at instruction #{} {}",
+        error.opcode_index, instruction
+    )
+    .unwrap();
+    let line_idx = error.chunk.unwrap_function().unwrap().opcode_to_line[error.opcode_index];
+    //native code (e.g. calls made through VM::call) has no line information
+    if let Some(line) = line_idx
+        .checked_sub(1)
+        .and_then(|idx| source.lines().nth(idx))
+    {
+        writeln!(result, "    at line {}: `{}`", line_idx, line).unwrap();
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::{Error, Interpreter};
+    use crate::data::objects::Value;
+    use crate::execution::module::Module;
+    use crate::execution::vm::InterpretErrorKind;
+
+    #[test]
+    fn interpreter_should_run_source_and_call_functions() {
+        let interpreter = Interpreter::new();
+        let module = Module::from_dot_notation("`TEST`");
+
+        let result = interpreter
+            .run_source("def square(x) = x * x\nsquare(3)", &module)
+            .unwrap();
+        assert_eq!(result, Value::Int(9));
+
+        let square = interpreter.get_global(&module, "square").unwrap();
+        let five = interpreter.to_value(&5).unwrap();
+        assert_eq!(
+            interpreter.call(square, vec![five]).unwrap(),
+            Value::Int(25)
+        );
+    }
+
+    #[test]
+    fn values_held_by_host_should_survive_collection() {
+        let interpreter = Interpreter::new();
+        let module = Module::from_dot_notation("`TEST`");
+
+        let held = interpreter.to_value(&vec!["a long string", "b"]).unwrap();
+        interpreter
+            .run_source(
+                "
+struct Garbage:
+    item
+
+for i in range(10000)
+    var g = Garbage(list(i))
+    g._0 = g
+",
+                &module,
+            )
+            .unwrap();
+        assert_eq!(
+            interpreter.from_value::<Vec<String>>(&held).unwrap(),
+            vec!["a long string", "b"]
+        );
+    }
+
+    #[test]
+    fn interpreter_should_report_errors() {
+        let interpreter = Interpreter::new();
+        let module = Module::from_dot_notation("`TEST`");
+
+        let error = interpreter.run_source("var x = (", &module).unwrap_err();
+        assert!(matches!(error, Error::Compile(..)));
+
+        let error = interpreter
+            .run_source("\nassert 1 == 2", &module)
+            .unwrap_err();
+        match error {
+            Error::Runtime(kind, message) => {
+                assert_eq!(kind, InterpretErrorKind::AssertionFailure);
+                assert!(message.contains("assert 1 == 2"));
+            }
+            other => panic!("expected runtime error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn errors_should_have_exit_codes() {
+        let mut interpreter = Interpreter::new();
+        interpreter.resolver_mut().set_script_dir("examples");
+        let module = Module::from_dot_notation("`TEST`");
+
+        let cases = [
+            ("exit(0)", 0),
+            ("exit(-7)", -7),
+            ("def f = exit(5)\nlist(1).map((x) => f())", 5),
+            ("var x = (", 2),
+            ("assert 1 == 2", 3),
+            ("1 + list()", 1),
+            ("exit(\"a\")", 1),
+            ("import failing.exit", 6),
+            ("import failing.assertion", 3),
+        ];
+        for (program, expected) in cases {
+            let error = interpreter.run_source(program, &module).unwrap_err();
+            assert_eq!(error.exit_code(), expected, "{}: {}", program, error);
+        }
+
+        let error = interpreter.run_source("exit(3)", &module).unwrap_err();
+        assert_eq!(error.to_string(), "exit(3)");
+    }
+
+    #[test]
+    fn main_function_should_receive_arguments() {
+        let interpreter = Interpreter::new();
+        let args = ["first".to_string(), "second".to_string()];
+        let result = interpreter
+            .run_main(Path::new("examples/entry_point.txt"), &args)
+            .unwrap();
+        assert_eq!(interpreter.from_value::<String>(&result).unwrap(), "second");
+
+        let interpreter = Interpreter::new();
+        let error = interpreter
+            .run_main(Path::new("examples/entry_point.txt"), &[])
+            .unwrap_err();
+        assert!(matches!(error, Error::Exit(4)), "{}", error);
+
+        //self-test of module runs when it is executed directly
+        let interpreter = Interpreter::new();
+        interpreter
+            .run_main(Path::new("std/loops.txt"), &[])
+            .unwrap();
+    }
+
+    #[test]
+    fn main_should_not_be_called_twice_or_when_it_is_not_function() {
+        let dir = std::env::temp_dir().join(format!("blop_main_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let cases = [
+            //script calls `main` on its own
+            ("var calls = 0\ndef main(args) =\n    calls = calls + 1\n    calls\nmain(args())", 1),
+            ("var calls = 0\ndef main() =\n    calls = calls + 1\ndef run = main()\nrun()\ncalls", 1),
+            //global `main` that is not a function is left alone
+            ("var main = 1\nmain + 1", 2),
+            //recursive `main` is still called by interpreter
+            ("def main(args) =\n    if args.len() == 0\n        5\n    else\n        main(list())", 5),
+        ];
+        for (source, expected) in cases {
+            let path = dir.join("script.txt");
+            std::fs::write(&path, source).unwrap();
+            let interpreter = Interpreter::new();
+            let result = interpreter.run_main(&path, &["arg".to_string()]).unwrap();
+            assert_eq!(result, Value::Int(expected), "{}", source);
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn every_module_should_define_main_flag() {
+        let interpreter = Interpreter::new();
+        let module = Module::from_dot_notation("`TEST`");
+        let result = interpreter.run_source("__main__", &module).unwrap();
+        assert_eq!(result, Value::Bool(true));
+
+        //module created by host is not entry module
+        let host = Module::from_dot_notation("host");
+        let value = interpreter.to_value(&1).unwrap();
+        interpreter.set_global(&host, "x", value);
+        let flag = interpreter.get_global(&host, "__main__").unwrap();
+        assert_eq!(flag, Value::Bool(false));
+    }
+
+    #[test]
+    fn interpreter_should_run_project() {
+        let interpreter = Interpreter::new();
+        //manifest is searched in parent directories as well
+        interpreter
+            .run_project(Path::new("examples/project/src"), &[])
+            .unwrap();
+
+        let error = Interpreter::new()
+            .run_project(Path::new("/"), &[])
+            .unwrap_err();
+        assert_eq!(error.to_string(), "blop.toml not found in /");
+    }
+}
@@ -0,0 +1,36 @@
+//! BLOP interpreter as a library.
+//!
+//! The way to embed the language is [`Interpreter`] which owns garbage collector, builtins and
+//! VM. Values it returns are [`Handle`]s that can not outlive it. Native functions registered on
+//! [`BuiltinMap`] work with plain [`Value`]s of the [`VM`] that calls them, so interpreter with
+//! custom builtins is created with `unsafe` [`Interpreter::with_builtins`].
+
+extern crate indexmap;
+extern crate regex;
+
+#[macro_use]
+extern crate lazy_static;
+
+mod compile;
+mod data;
+mod execution;
+mod interpreter;
+mod parsing;
+#[cfg(test)]
+mod test;
+
+pub use data::conversion::{from_value, ConversionError};
+pub use data::objects::{
+    pretty_format, EnumDescriptor, OwnedObjectItem, StackObject, StructDescriptor, StructInstance,
+    VMap, VVec, Value,
+};
+pub use execution::arity::Arity;
+pub use execution::builtins::{
+    builtin_factory, BuiltinError, BuiltinFunction, BuiltinMap, BuiltinMethod, BuiltinResult,
+    NativeModule,
+};
+pub use execution::manifest::Manifest;
+pub use execution::module::Module;
+pub use execution::resolver::{ModuleResolver, ModuleSource};
+pub use execution::vm::{InterpretErrorKind, VM};
+pub use interpreter::{Error, Handle, Interpreter};
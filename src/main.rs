@@ -1,97 +1,86 @@
-use crate::data::gc::GC;
-
-use crate::execution::builtins::builtin_factory;
-use crate::execution::chunk::Chunk;
-use crate::execution::module::{compile_file, compile_program, Module};
-use crate::execution::vm::VM;
-use crate::parsing::ast::Expr;
-use execution::chunk::Opcode;
-use execution::vm::InterpretError;
+use blop::{Error, Interpreter, Module};
 
 use std::env;
-use std::fmt::Write;
 use std::io::{stdin, BufRead};
 use std::path::Path;
+use std::process;
 #[cfg(feature = "bench")]
 use std::time::Instant;
 
-extern crate indexmap;
-extern crate regex;
-
-#[macro_use]
-extern crate lazy_static;
-
-mod compile;
-mod data;
-mod execution;
-mod parsing;
-#[cfg(test)]
-mod test;
+const USAGE: &str =
+    "usage: blop [-I dir]... [file [arg]...]\n       blop run [-I dir]... [project dir [arg]...]";
 
 fn main() {
-    let args = env::args().collect::<Vec<_>>();
-    if args.len() != 2 {
-        run_repl();
-        return;
+    let mut interpreter = Interpreter::new();
+    let mut filename = None;
+
+    let mut args = env::args().skip(1).peekable();
+    let run_project = args.next_if(|arg| arg == "run").is_some();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-I" => match args.next() {
+                Some(dir) => interpreter.add_include_dir(dir),
+                None => {
+                    eprintln!("missing directory after -I\n{}", USAGE);
+                    process::exit(2);
+                }
+            },
+            dir if dir.starts_with("-I") => interpreter.add_include_dir(&dir[2..]),
+            //everything after script is passed to its `main`
+            _ => {
+                filename = Some(arg);
+                break;
+            }
+        }
     }
-    let filename = args.get(1).unwrap();
+    let script_args = args.collect::<Vec<_>>();
 
-    let mut gc = unsafe { GC::default_gc() };
-    let builtins = builtin_factory();
-
-    let mut vm = VM::new(&mut gc, &builtins);
+    if run_project {
+        let dir = filename.unwrap_or_else(|| ".".to_string());
+        if let Err(error) = interpreter.run_project(Path::new(&dir), &script_args) {
+            exit_with_error(error);
+        }
+        return;
+    }
 
-    let (source, pointer) = compile_file(Path::new(filename), &mut vm).unwrap();
+    let filename = match filename {
+        Some(filename) => filename,
+        None => {
+            run_repl(interpreter);
+            return;
+        }
+    };
 
     println!("running");
 
     #[cfg(feature = "bench")]
     let start_time = Instant::now();
 
-    let _ = vm
-        .run(pointer)
-        .map_err(|error| eprintln!("\n{}", display_error(source.as_str(), error)))
-        .unwrap();
+    let result = interpreter.run_main(Path::new(&filename), &script_args);
     #[cfg(feature = "bench")]
     {
         let end_time = Instant::now();
         println!("{:?}", end_time - start_time);
     }
+    if let Err(error) = result {
+        exit_with_error(error);
+    }
 }
 
-fn display_error(source: &str, error: InterpretError) -> String {
-    let mut result = String::new();
-    writeln!(result, "error: {:?}", error.kind).unwrap();
-    let instruction: Opcode = error.chunk.unwrap_function().unwrap().code[error.opcode_index];
-    writeln!(
-        result,
-        "    at instruction #{} {}",
-        error.opcode_index, instruction
-    )
-    .unwrap();
-    let line_idx = error.chunk.unwrap_function().unwrap().opcode_to_line[error.opcode_index];
-    writeln!(
-        result,
-        "    at line {}: `{}`",
-        line_idx,
-        source.lines().nth(line_idx - 1).unwrap()
-    )
-    .unwrap();
-
-    result
+/// reports error (unless script called `exit` itself) and exits with its code
+fn exit_with_error(error: Error) -> ! {
+    if !matches!(error, Error::Exit(_)) {
+        eprintln!("\n{}", error);
+    }
+    process::exit(error.exit_code())
 }
 
-pub fn run_repl() {
+pub fn run_repl(interpreter: Interpreter) {
     let stdin = stdin();
     let mut stdin = stdin.lock();
     let mut input = String::new();
     let mut buffer = String::new();
 
-    let mut gc = unsafe { GC::default_gc() };
-    let builtins = builtin_factory();
-    //VM is guranteed to work separately from compiler, so two borrows actually do not happen
-    let mut vm = VM::new(&mut gc, &builtins);
-
     let module = Module::from_dot_notation("`REPL`");
 
     loop {
@@ -102,22 +91,14 @@ pub fn run_repl() {
                 break;
             }
 
-            x if x.is_empty() => {
+            "" => {
                 println!("```\n{}\n```", input);
 
-                let ptr = match compile_program(input.clone(), &module, &mut vm) {
-                    Ok(p) => p,
-                    Err(e) => {
-                        println!("error!\n{e}");
-                        input = String::new();
-                        continue;
-                    }
-                };
-
-                match vm.run(ptr).map_err(|e| crate::display_error(&input, e)) {
+                match interpreter.run_source(&input, &module) {
                     Ok(value) => {
                         println!("Ok. result: {}", value);
                     }
+                    Err(Error::Exit(code)) => process::exit(code),
                     Err(e) => {
                         println!("error!");
                         println!("{}", e);
@@ -13,6 +13,8 @@ pub enum Stmt {
     PropertyAssignment(Expr, Expr),
     Expression(Expr),
     Assert(Token, Expr),
+    /// `yield value`, turns enclosing function into generator
+    Yield(Token, Expr),
     Pass(Token),
     FunctionDeclaration {
         name: Token,
@@ -35,20 +37,42 @@ pub enum Stmt {
         implementations: Vec<Stmt>,
     },
 
+    /// `import a.b.c [as x]` and `from a.b import c [as x], d` bind global `c` of module `a.b`
+    /// (or submodule `a.b.c` if there is no such global). `module` is empty for `import a`,
+    /// which binds module `a` itself. `level` is the number of leading dots of relative import
+    /// (`import ..a.b`), 0 for absolute one
     Import {
+        level: usize,
         module: Vec<Token>,
-        name: Token,
-        rename: Option<Token>,
+        names: Vec<(Token, Option<Token>)>,
+    },
+
+    /// `from a.b import *`, binds every global of module `a.b`
+    ImportAll {
+        level: usize,
+        module: Vec<Token>,
+        star: Token,
+    },
+
+    /// `for variable in iterable` loop, `keyword` identifies scope of loop variable
+    For {
+        keyword: Token,
+        variable: Token,
+        iterable: Expr,
+        body: Expr,
     },
 }
 
 #[derive(Clone, Debug)]
 pub enum Expr {
+    /// integer literal, either `Number` or `BigNumber` token
     Number(Token),
     FloatNumber(Token),
     Bool(Token),
     Name(Token),
     ConstString(Token),
+    /// interpolated string `f"..."`, parts are literals (`ConstString`) and arbitrary expressions
+    Interpolation(Token, Vec<Expr>),
     Binary(Token, Box<Expr>, Box<Expr>),
     Unary(Token, Box<Expr>),
     If(Box<Expr>, Box<Expr>, Option<Box<Expr>>),
@@ -5,6 +5,7 @@ use std::iter::Peekable;
 use std::mem;
 use std::str::CharIndices;
 
+use num_bigint::BigInt;
 use ordered_float::NotNan;
 
 #[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
@@ -37,6 +38,14 @@ pub enum TokenKind {
     Slash,
     Mod,
     Power,
+    FloorDiv,
+
+    BitAnd,
+    BitOr,
+    BitXor,
+    BitNot,
+    ShiftLeft,
+    ShiftRight,
 
     And,
     Or,
@@ -61,10 +70,19 @@ pub enum TokenKind {
     Semicolon,
 
     Number(i64),
+    /// integer literal that does not fit into `Number`
+    BigNumber(BigInt),
     FloatNumber(NotNan<f64>),
     Name(String),
     ConstString(String),
 
+    /// start of interpolated string `f"..."`, followed by literal parts (`ConstString`) and
+    /// expressions wrapped in `InterpolationStart`/`InterpolationEnd`
+    FStringStart,
+    FStringEnd,
+    InterpolationStart,
+    InterpolationEnd,
+
     True,
     False,
 
@@ -79,7 +97,11 @@ pub enum TokenKind {
     Enum,
     Impl,
     Import,
+    From,
     As,
+    For,
+    In,
+    Yield,
 }
 
 impl Display for TokenKind {
@@ -104,6 +126,13 @@ impl Display for TokenKind {
                 TokenKind::Dot => ".".to_string(),
                 TokenKind::QuestionMark => "?".to_string(),
                 TokenKind::Power => "**".to_string(),
+                TokenKind::FloorDiv => "//".to_string(),
+                TokenKind::BitAnd => "&".to_string(),
+                TokenKind::BitOr => "|".to_string(),
+                TokenKind::BitXor => "^".to_string(),
+                TokenKind::BitNot => "~".to_string(),
+                TokenKind::ShiftLeft => "<<".to_string(),
+                TokenKind::ShiftRight => ">>".to_string(),
                 TokenKind::Or => "or".to_string(),
                 TokenKind::And => "and".to_string(),
                 TokenKind::Not => "not".to_string(),
@@ -130,6 +159,15 @@ impl Token {
         }
     }
 
+    /// value of `Number` or `BigNumber` token
+    pub fn get_integer(&self) -> Option<BigInt> {
+        match &self.kind {
+            TokenKind::Number(n) => Some(BigInt::from(*n)),
+            TokenKind::BigNumber(n) => Some(n.clone()),
+            _ => None,
+        }
+    }
+
     pub fn get_float(&self) -> Option<f64> {
         match &self.kind {
             &TokenKind::FloatNumber(n) => Some(n.into_inner()),
@@ -155,7 +193,7 @@ struct Lexer<'input> {
 }
 
 impl<'input> Lexer<'input> {
-    fn new(input_string: &str) -> Lexer {
+    fn new(input_string: &str) -> Lexer<'_> {
         use self::TokenKind::*;
         let keywords = vec![
             ("assert", Assert),
@@ -176,7 +214,11 @@ impl<'input> Lexer<'input> {
             ("true", True),
             ("false", False),
             ("import", Import),
+            ("from", From),
             ("as", As),
+            ("for", For),
+            ("in", In),
+            ("yield", Yield),
         ]
         .into_iter()
         .map(|(k, v)| (k.to_string(), v))
@@ -185,8 +227,11 @@ impl<'input> Lexer<'input> {
         let simple_tokens = vec![
             ('+', Plus),
             ('-', Minus),
-            // * is not here because it may also be **
-            ('/', Slash),
+            // * and / are not here because they may also be ** and //
+            ('&', BitAnd),
+            ('|', BitOr),
+            ('^', BitXor),
+            ('~', BitNot),
             (',', Comma),
             ('.', Dot),
             ('?', QuestionMark),
@@ -323,6 +368,20 @@ impl<'input> Lexer<'input> {
                     }
                 }
 
+                '/' => {
+                    let index = self.compute_index();
+                    self.input_iterator.next();
+                    match self.input_iterator.peek() {
+                        Some((_, '/')) => {
+                            result.push(token!(index, FloorDiv));
+                            self.input_iterator.next();
+                        }
+                        _ => {
+                            result.push(token!(index, Slash));
+                        }
+                    }
+                }
+
                 x if self.simple_tokens.contains_key(&x) => {
                     result.push(token!(self.simple_tokens.get(&x).cloned().unwrap()));
                     self.input_iterator.next();
@@ -347,11 +406,27 @@ impl<'input> Lexer<'input> {
                     } else {
                         let end_idx = self.compute_input_shift();
                         let string = self.input_string[start_idx..end_idx].to_string();
-                        let number: i64 = string.parse().unwrap();
-                        result.push(token!(token_index, Number(number)));
+                        let kind = match string.parse::<i64>() {
+                            Ok(number) => Number(number),
+                            Err(_) => BigNumber(string.parse().unwrap()),
+                        };
+                        result.push(token!(token_index, kind));
                     }
                 }
 
+                'r' if self.input_string[self.compute_input_shift()..].starts_with("r\"") => {
+                    let token_index = self.compute_index();
+                    self.input_iterator.next(); //skip r
+                    let s = self.read_string_literal(token_index, true)?;
+                    result.push(token!(token_index, TokenKind::ConstString(s)));
+                }
+
+                'f' if self.input_string[self.compute_input_shift()..].starts_with("f\"") => {
+                    let token_index = self.compute_index();
+                    self.input_iterator.next(); //skip f
+                    self.read_fstring(token_index, &mut result)?;
+                }
+
                 x if x.is_alphabetic() || x == '_' => {
                     let start_idx = self.compute_input_shift();
                     let token_index = self.compute_index();
@@ -378,15 +453,7 @@ impl<'input> Lexer<'input> {
 
                 '"' => {
                     let token_index = self.compute_index();
-                    self.input_iterator.next(); //skip opening "
-                    let start_idx = self.compute_input_shift();
-                    self.read_while(&|c| c != '"');
-                    if self.input_iterator.peek().is_none() {
-                        return Err(format!("unterminated string at [{}]", token_index));
-                    }
-                    let end_idx = self.compute_input_shift();
-                    self.input_iterator.next(); //skip closing "
-                    let s = self.input_string[start_idx..end_idx].to_string();
+                    let s = self.read_string_literal(token_index, false)?;
                     result.push(token!(token_index, TokenKind::ConstString(s)));
                 }
 
@@ -469,6 +536,11 @@ impl<'input> Lexer<'input> {
                             result.push(token!(possible_token_index, CompareLessEqual));
                             self.input_iterator.next(); //skip =
                         }
+                        Some((_, '<')) => {
+                            // <<
+                            result.push(token!(possible_token_index, ShiftLeft));
+                            self.input_iterator.next(); //skip second <
+                        }
                         _ => {
                             result.push(token!(possible_token_index, CompareLess));
                         }
@@ -484,6 +556,11 @@ impl<'input> Lexer<'input> {
                             result.push(token!(possible_token_index, CompareGreaterEqual));
                             self.input_iterator.next(); //skip =
                         }
+                        Some((_, '>')) => {
+                            // >>
+                            result.push(token!(possible_token_index, ShiftRight));
+                            self.input_iterator.next(); //skip second >
+                        }
                         _ => {
                             result.push(token!(possible_token_index, CompareGreater));
                         }
@@ -538,6 +615,259 @@ impl<'input> Lexer<'input> {
         }
     }
 
+    /// reads string literal starting at opening quote. Triple-quoted strings may span multiple
+    /// lines, their content never produces indentation tokens. Raw strings keep backslashes as is
+    fn read_string_literal(&mut self, token_index: Index, raw: bool) -> Result<String, String> {
+        let is_triple = self.input_string[self.compute_input_shift()..].starts_with("\"\"\"");
+        let quotes = if is_triple { 3 } else { 1 };
+        for _ in 0..quotes {
+            self.input_iterator.next(); //skip opening quotes
+        }
+
+        let mut result = String::new();
+        loop {
+            let rest = &self.input_string[self.compute_input_shift()..];
+            if is_triple && rest.starts_with("\"\"\"") || !is_triple && rest.starts_with('"') {
+                for _ in 0..quotes {
+                    self.input_iterator.next(); //skip closing quotes
+                }
+                return Ok(result);
+            }
+
+            let escape_index = self.compute_index();
+            match self.input_iterator.next() {
+                None => return Err(format!("unterminated string at [{}]", token_index)),
+
+                Some((idx, '\n')) => {
+                    if !is_triple {
+                        return Err(format!(
+                            "unterminated string at [{}] (use triple quotes for multi-line strings)",
+                            token_index
+                        ));
+                    }
+                    self.line_number += 1;
+                    self.line_start = idx + 1;
+                    result.push('\n');
+                }
+
+                Some((_, '\\')) if !raw => result.push(self.read_escape(escape_index)?),
+
+                Some((_, c)) => result.push(c),
+            }
+        }
+    }
+
+    /// reads interpolated string starting at opening quote, pushing its parts to `result`
+    fn read_fstring(&mut self, token_index: Index, result: &mut Vec<Token>) -> Result<(), String> {
+        let is_triple = self.input_string[self.compute_input_shift()..].starts_with("\"\"\"");
+        let quotes = if is_triple { 3 } else { 1 };
+        for _ in 0..quotes {
+            self.input_iterator.next(); //skip opening quotes
+        }
+
+        result.push(Token {
+            position: token_index,
+            kind: TokenKind::FStringStart,
+        });
+
+        let mut literal = String::new();
+        let mut literal_index = self.compute_index();
+
+        macro_rules! flush_literal {
+            () => {
+                if !literal.is_empty() {
+                    result.push(Token {
+                        position: literal_index,
+                        kind: TokenKind::ConstString(mem::take(&mut literal)),
+                    });
+                }
+            };
+        }
+
+        loop {
+            let rest = &self.input_string[self.compute_input_shift()..];
+            if is_triple && rest.starts_with("\"\"\"") || !is_triple && rest.starts_with('"') {
+                flush_literal!();
+                let end_index = self.compute_index();
+                for _ in 0..quotes {
+                    self.input_iterator.next(); //skip closing quotes
+                }
+                result.push(Token {
+                    position: end_index,
+                    kind: TokenKind::FStringEnd,
+                });
+                return Ok(());
+            }
+
+            let char_index = self.compute_index();
+            match self.input_iterator.next() {
+                None => return Err(format!("unterminated string at [{}]", token_index)),
+
+                Some((idx, '\n')) => {
+                    if !is_triple {
+                        return Err(format!(
+                            "unterminated string at [{}] (use triple quotes for multi-line strings)",
+                            token_index
+                        ));
+                    }
+                    self.line_number += 1;
+                    self.line_start = idx + 1;
+                    literal.push('\n');
+                }
+
+                Some((_, '\\')) => literal.push(self.read_escape(char_index)?),
+
+                Some((_, '{')) if matches!(self.input_iterator.peek(), Some((_, '{'))) => {
+                    self.input_iterator.next();
+                    literal.push('{');
+                }
+
+                Some((_, '}')) if matches!(self.input_iterator.peek(), Some((_, '}'))) => {
+                    self.input_iterator.next();
+                    literal.push('}');
+                }
+
+                Some((_, '}')) => {
+                    return Err(format!(
+                        "single }} is not allowed in f-string (use }}}} instead) at [{}]",
+                        char_index
+                    ))
+                }
+
+                Some((_, '{')) => {
+                    flush_literal!();
+                    self.read_interpolation(char_index, result)?;
+                    literal_index = self.compute_index();
+                }
+
+                Some((_, c)) => literal.push(c),
+            }
+        }
+    }
+
+    /// reads expression of f-string placeholder (after `{`) and tokenizes it
+    fn read_interpolation(
+        &mut self,
+        open_index: Index,
+        result: &mut Vec<Token>,
+    ) -> Result<(), String> {
+        self.read_while(&|c| c == ' ');
+        let start_idx = self.compute_input_shift();
+        let expression_index = self.compute_index();
+
+        //strings inside of expression may contain braces
+        let mut is_inside_string = false;
+        loop {
+            match self.input_iterator.peek().copied() {
+                None => {
+                    return Err(format!(
+                        "unterminated f-string placeholder at [{}]",
+                        open_index
+                    ))
+                }
+                Some((_, '\n')) => {
+                    return Err(format!(
+                        "f-string placeholder at [{}] should not contain newlines",
+                        open_index
+                    ))
+                }
+                Some((_, '}')) if !is_inside_string => break,
+                Some((_, '"')) => is_inside_string = !is_inside_string,
+                Some((_, '\\')) if is_inside_string => {
+                    self.input_iterator.next();
+                }
+                _ => {}
+            }
+            self.input_iterator.next();
+        }
+
+        let end_idx = self.compute_input_shift();
+        self.input_iterator.next(); //skip }
+
+        let source = self.input_string[start_idx..end_idx].trim_end();
+        if source.is_empty() {
+            return Err(format!("empty f-string placeholder at [{}]", open_index));
+        }
+
+        let tokens = Lexer::new(source)
+            .tokenize()
+            .map_err(|e| format!("{} (in f-string placeholder at [{}])", e, expression_index))?;
+
+        result.push(Token {
+            position: open_index,
+            kind: TokenKind::InterpolationStart,
+        });
+        //placeholder is always single line, so only columns need to be shifted
+        result.extend(
+            tokens
+                .into_iter()
+                .filter(|t| {
+                    !matches!(
+                        t.kind,
+                        TokenKind::BeginBlock | TokenKind::EndBlock | TokenKind::LineEnd
+                    )
+                })
+                .map(|t| Token {
+                    position: Index(expression_index.0, expression_index.1 + t.position.1 - 1),
+                    kind: t.kind,
+                }),
+        );
+        result.push(Token {
+            position: self.compute_index(),
+            kind: TokenKind::InterpolationEnd,
+        });
+
+        Ok(())
+    }
+
+    /// reads escape sequence after backslash
+    fn read_escape(&mut self, escape_index: Index) -> Result<char, String> {
+        let escaped = match self.input_iterator.next() {
+            Some((_, 'n')) => '\n',
+            Some((_, 't')) => '\t',
+            Some((_, 'r')) => '\r',
+            Some((_, '0')) => '\0',
+            Some((_, '\\')) => '\\',
+            Some((_, '"')) => '"',
+            Some((_, 'u')) => {
+                if !matches!(self.input_iterator.next(), Some((_, '{'))) {
+                    return Err(format!(
+                        "expected {{ in unicode escape at [{}]",
+                        escape_index
+                    ));
+                }
+                let start_idx = self.compute_input_shift();
+                self.read_while(&|c| c.is_ascii_hexdigit());
+                let end_idx = self.compute_input_shift();
+                if !matches!(self.input_iterator.next(), Some((_, '}'))) {
+                    return Err(format!(
+                        "expected }} in unicode escape at [{}]",
+                        escape_index
+                    ));
+                }
+                let digits = &self.input_string[start_idx..end_idx];
+                return u32::from_str_radix(digits, 16)
+                    .ok()
+                    .filter(|_| digits.len() <= 6)
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| {
+                        format!(
+                            "invalid unicode escape \\u{{{}}} at [{}]",
+                            digits, escape_index
+                        )
+                    });
+            }
+            Some((_, other)) => {
+                return Err(format!(
+                    "invalid escape sequence \\{} at [{}]",
+                    other, escape_index
+                ))
+            }
+            None => return Err(format!("unterminated string at [{}]", escape_index)),
+        };
+        Ok(escaped)
+    }
+
     fn read_identation(&mut self) -> Result<Vec<Token>, String> {
         use TokenKind::{BeginBlock, EndBlock};
         let mut result = vec![];
@@ -606,3 +936,139 @@ impl<'input> Lexer<'input> {
         Ok(result)
     }
 }
+
+#[cfg(test)]
+mod string_literal_tests {
+    use rstest::*;
+
+    use super::{tokenize, TokenKind};
+
+    fn strings(input: &str) -> Vec<String> {
+        tokenize(input)
+            .unwrap()
+            .into_iter()
+            .filter_map(|t| match t.kind {
+                TokenKind::ConstString(s) => Some(s),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[rstest]
+    #[case(r#""a\nb\t\\\"""#, "a\nb\t\\\"")]
+    #[case(r#""\u{48}\u{e9}\u{1F600}""#, "H\u{e9}\u{1F600}")]
+    #[case(r#"r"C:\path\n""#, r"C:\path\n")]
+    #[case(
+        "\"\"\"line 1\n  \"quoted\"\nline 3\"\"\"",
+        "line 1\n  \"quoted\"\nline 3"
+    )]
+    #[case("r\"\"\"raw \\n\n\"\"\"", "raw \\n\n")]
+    fn escapes_should_be_processed(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(strings(input), vec![expected.to_string()]);
+    }
+
+    #[rstest]
+    #[case(r#""\q""#, "invalid escape sequence \\q at [1:2]")]
+    #[case(r#""\u{110000}""#, "invalid unicode escape \\u{110000} at [1:2]")]
+    #[case(r#""\u{}""#, "invalid unicode escape \\u{} at [1:2]")]
+    #[case(r#""\u41""#, "expected { in unicode escape at [1:2]")]
+    #[case(
+        "\"abc\ndef\"",
+        "unterminated string at [1:1] (use triple quotes for multi-line strings)"
+    )]
+    #[case("\"\"\"abc", "unterminated string at [1:1]")]
+    fn invalid_literals_should_be_rejected(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(tokenize(input).unwrap_err(), expected);
+    }
+
+    #[rstest]
+    #[case("f\"{x\"", "unterminated f-string placeholder at [1:3]")]
+    #[case("f\"{  }\"", "empty f-string placeholder at [1:3]")]
+    #[case(
+        "f\"a}\"",
+        "single } is not allowed in f-string (use }} instead) at [1:4]"
+    )]
+    #[case(
+        "f\"{x\ny}\"",
+        "f-string placeholder at [1:3] should not contain newlines"
+    )]
+    #[case(
+        "f\"{x $ y}\"",
+        "unexpected character $ at 1:3 (in f-string placeholder at [1:4])"
+    )]
+    fn invalid_fstrings_should_be_rejected(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(tokenize(input).unwrap_err(), expected);
+    }
+
+    #[test]
+    fn fstring_should_be_split_into_parts() {
+        use TokenKind::*;
+        let tokens = tokenize("f\"a{ x + 1 }{{b}}\"").unwrap();
+        assert_eq!(
+            tokens.iter().map(|t| t.kind.clone()).collect::<Vec<_>>(),
+            vec![
+                BeginBlock,
+                FStringStart,
+                ConstString("a".to_string()),
+                InterpolationStart,
+                Name("x".to_string()),
+                Plus,
+                Number(1),
+                InterpolationEnd,
+                ConstString("{b}".to_string()),
+                FStringEnd,
+                EndBlock
+            ]
+        );
+        //positions of placeholder tokens point inside of string
+        assert_eq!(tokens[4].position, super::Index(1, 6));
+        assert_eq!(tokens[6].position, super::Index(1, 10));
+    }
+
+    #[test]
+    fn operators_should_be_tokenized() {
+        use TokenKind::*;
+        let tokens = tokenize("a // b / c << d >> e <= f & g | h ^ ~i").unwrap();
+        let operators: Vec<_> = tokens
+            .into_iter()
+            .map(|t| t.kind)
+            .filter(|kind| !matches!(kind, Name(_) | BeginBlock | EndBlock))
+            .collect();
+        assert_eq!(
+            operators,
+            vec![
+                FloorDiv,
+                Slash,
+                ShiftLeft,
+                ShiftRight,
+                CompareLessEqual,
+                BitAnd,
+                BitOr,
+                BitXor,
+                BitNot
+            ]
+        );
+    }
+
+    #[test]
+    fn multiline_strings_should_not_produce_indentation() {
+        let tokens = tokenize("var s = \"\"\"\n    indented\n\"\"\"\nvar x = 1")
+            .unwrap()
+            .into_iter()
+            .map(|t| t.kind)
+            .collect::<Vec<_>>();
+
+        assert!(!tokens[1..tokens.len() - 1].contains(&TokenKind::BeginBlock));
+        assert_eq!(
+            tokens.iter().filter(|t| **t == TokenKind::LineEnd).count(),
+            1
+        );
+        //line numbers keep counting inside of string
+        let tokens = tokenize("\"\"\"a\nb\"\"\"\nx").unwrap();
+        let x = tokens
+            .iter()
+            .find(|t| t.kind == TokenKind::Name("x".to_string()))
+            .unwrap();
+        assert_eq!(x.position.0, 3);
+    }
+}
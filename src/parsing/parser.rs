@@ -45,7 +45,9 @@ peg::parser! {
             / import_stmt()
             / assignment_stmt()
             / assert_stmt()
+            / yield_stmt()
             / pass_stmt()
+            / for_stmt()
             / e:expr() {Stmt::Expression(e)}
 
 
@@ -147,24 +149,43 @@ peg::parser! {
         rule import_stmt() -> Stmt =
             [t!(Import)] trg:import_target() rename:import_rename()? {
                 Stmt::Import{
-                    module: trg.0,
-                    name: trg.1,
-                    rename
+                    level: trg.0,
+                    module: trg.1,
+                    names: vec![(trg.2, rename)]
+                }
+            }
+            / [t!(From)] module:from_module() [t!(Import)] [star@t!(Star)] {
+                Stmt::ImportAll{
+                    level: module.0,
+                    module: module.1,
+                    star: star.clone()
+                }
+            }
+            / [t!(From)] module:from_module() [t!(Import)] names:import_item() ++ [t!(Comma)] {
+                Stmt::Import{
+                    level: module.0,
+                    module: module.1,
+                    names
                 }
             }
 
-        rule import_target() -> (Vec<Token>, Token) =
-            item0: name() [t!(Dot)] more_items:name() ++ [t!(Dot)]
+        // `a.b.c`, `.b.c` or `..c`, leading dots make import relative to importing module
+        rule import_target() -> (usize, Vec<Token>, Token) =
+            dots:[t!(Dot)]* items: name() ++ [t!(Dot)]
              {
-                let mut more_items = more_items;
-                 //modname:name() ++ [t!(Dot)] [t!(Dot)] item_name:name()
-                let mut items = vec![item0];
-                items.append(&mut more_items);
-
+                let mut items = items;
                 let import_name = items.pop().unwrap();
-                (items, import_name)
+                (dots.len(), items, import_name)
             }
 
+        // module of `from` import, relative one may consist of dots only (`from .. import x`)
+        rule from_module() -> (usize, Vec<Token>) =
+            dots:[t!(Dot)]+ items:name() ** [t!(Dot)] {(dots.len(), items)}
+            / items:name() ++ [t!(Dot)] {(0, items)}
+
+        rule import_item() -> (Token, Option<Token>) =
+            n:name() rename:import_rename()? {(n, rename)}
+
 
         rule import_rename() -> Token =
             [t!(As)] n:name() {n}
@@ -197,6 +218,9 @@ peg::parser! {
         rule assert_stmt() -> Stmt =
             [a@t!(Assert)] e:expr() {Stmt::Assert(a.clone(), e)}
 
+        rule yield_stmt() -> Stmt =
+            [y@t!(Yield)] e:expr() {Stmt::Yield(y.clone(), e)}
+
         rule if_expr() -> Expr =
             if_elif_else()/ if_elif() / if_then()
 
@@ -229,6 +253,16 @@ peg::parser! {
                 (elif_cond, elif_body)
             }
 
+        rule for_stmt() -> Stmt =
+            [k@t!(For)] v:name() [t!(In)] iterable:simple_expr() body:expr() {
+                Stmt::For {
+                    keyword: k.clone(),
+                    variable: v,
+                    iterable,
+                    body
+                }
+            }
+
         rule pass_stmt() -> Stmt =
             [t@t!(Pass)] {Stmt::Pass(t.clone())}
 
@@ -286,6 +320,20 @@ peg::parser! {
                 {bin!(op, x, y)}
 
             --
+            x: (@) [op@t!(BitOr)] y:@
+                {bin!(op, x, y)}
+            --
+            x: (@) [op@t!(BitXor)] y:@
+                {bin!(op, x, y)}
+            --
+            x: (@) [op@t!(BitAnd)] y:@
+                {bin!(op, x, y)}
+            --
+            x: (@) [op@t!(ShiftLeft)] y:@
+                {bin!(op, x, y)}
+            x: (@) [op@t!(ShiftRight)] y:@
+                {bin!(op, x, y)}
+            --
             x: (@) [op@t!(Plus)] y:@
                 {bin!(op, x, y)}
             x: (@) [op@t!(Minus)] y:@
@@ -297,6 +345,21 @@ peg::parser! {
                 {bin!(op, x, y)}
             x: (@) [op@t!(Mod)] y:@
                 {bin!(op, x, y)}
+            x: (@) [op@t!(FloorDiv)] y:@
+                {bin!(op, x, y)}
+            --
+            [op@t!(BitNot)] x: @
+                {
+                    Expr::Unary(op.clone(), Box::new(x))
+                }
+            [op@t!(Minus)] x: @
+                {
+                    Expr::Unary(op.clone(), Box::new(x))
+                }
+            [op@t!(Plus)] x: @
+                {
+                    Expr::Unary(op.clone(), Box::new(x))
+                }
             --
             x:@ [op@t!(Power)] y:(@)
                 {bin!(op, x, y)}
@@ -357,15 +420,23 @@ peg::parser! {
 
         rule term() -> Expr
             = [num@t!(Number(..))] {Expr::Number(num.clone())}
+            / [num@t!(BigNumber(..))] {Expr::Number(num.clone())}
             / [num @ t!(FloatNumber(..))] {Expr::FloatNumber(num.clone())}
             / [b@t!(True) | b@t!(False)] {Expr::Bool(b.clone())}
             / t:name()
                 {Expr::Name(t)}
             / [s@t!(ConstString(..))] {Expr::ConstString(s.clone())}
+            / [start@t!(FStringStart)] parts:interpolation_part()* [t!(FStringEnd)] {
+                Expr::Interpolation(start.clone(), parts)
+            }
             / [t!(LParen)] e:expr() [t!(RParen)] {e}
 
 
 
+        rule interpolation_part() -> Expr =
+            [s@t!(ConstString(..))] {Expr::ConstString(s.clone())}
+            / [t!(InterpolationStart)] e:expr() [t!(InterpolationEnd)] {e}
+
         rule name() -> Token
             = [t@Token{kind:TokenKind::Name(..), position:pos}] {t.clone()}
     }
@@ -1,6 +1,48 @@
-use super::execution::module::{compile_file, run_file};
+use super::data::gc::GC;
+use super::data::objects::Value;
+use super::execution::builtins::{builtin_factory, BuiltinMap};
+use super::execution::module::{compile_file, compile_program, run_file, Module};
+use super::execution::vm::{InterpretError, VM};
 use std::path::Path;
 
+/// module that test programs are compiled into
+pub(crate) fn test_module() -> Module {
+    Module::from_dot_notation("`TEST`")
+}
+
+/// runs `test` with a fresh VM that uses given builtins
+pub(crate) fn with_builtins(builtins: &BuiltinMap, test: impl FnOnce(&mut VM)) {
+    let mut gc = unsafe { GC::default_gc() };
+    let mut vm = VM::new(&mut gc, builtins);
+    test(&mut vm);
+}
+
+/// runs `test` with a fresh VM that uses default builtins
+pub(crate) fn with_vm(test: impl FnOnce(&mut VM)) {
+    with_builtins(&builtin_factory(), test)
+}
+
+/// compiles program into test module and runs it, panics if program does not compile
+pub(crate) fn run_program(vm: &mut VM, program: &str) -> Result<Value, InterpretError> {
+    let pointer = compile_program(program.to_string(), &test_module(), vm).unwrap();
+    vm.run(pointer)
+}
+
+/// runs program that is expected to fail and returns debug representation of its error
+pub(crate) fn run_error(program: &str) -> String {
+    let mut error = String::new();
+    with_vm(|vm| error = format!("{:?}", run_program(vm, program).unwrap_err()));
+    error
+}
+
+/// checks that every program fails with error containing expected message
+pub(crate) fn assert_errors<'a>(cases: impl IntoIterator<Item = (&'a str, &'a str)>) {
+    for (program, expected) in cases {
+        let error = run_error(program);
+        assert!(error.contains(expected), "{}: {}", program, error);
+    }
+}
+
 macro_rules! test_file {
     ($name:ident) => {
         #[test]
@@ -35,20 +77,14 @@ macro_rules! test_fail_compile {
     ($name:ident) => {
         #[test]
         fn $name() {
-            use crate::data::gc::GC;
-            use crate::execution::builtins::builtin_factory;
-            use crate::execution::vm::VM;
             let mut path = String::new();
             path.push_str("examples/");
             path.push_str(stringify!($name));
             path.push_str(".txt");
 
-            let mut gc = unsafe { GC::default_gc() };
-            let builtins = builtin_factory();
-
-            let mut vm = VM::new(&mut gc, &builtins);
-
-            compile_file(Path::new(&path), &mut vm).err().unwrap();
+            with_vm(|vm| {
+                compile_file(Path::new(&path), vm).err().unwrap();
+            });
         }
     };
 }
@@ -104,3 +140,33 @@ test_file! {enums}
 test_file! {inline_blocks}
 
 test_file! {imports}
+
+test_file! {json}
+
+test_file! {string_methods}
+
+test_file! {string_literals}
+
+test_file! {interpolation}
+
+test_file! {vector_methods}
+
+test_file! {iterators}
+
+test_file! {generators}
+
+test_file! {math}
+
+test_file! {bigints}
+
+test_file! {bitwise}
+
+test_file! {negation}
+
+test_file! {search_path}
+
+test_file! {module_imports}
+
+test_file! {relative_imports}
+
+test_file! {entry_point}